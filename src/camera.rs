@@ -1,15 +1,42 @@
-use bevy::prelude::*;
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
 
 use crate::{
-    app::AppState,
+    app::{AppState, AppUpdate},
     chunk::{Chunk, ChunkLoader},
+    cursor::CurrsorPositon,
+    input::{ActionMap, InputAction, InputActionAppExt, InputBinding, view_input_allowed},
+    player::view::{FollowTarget, MoveActivePlayerView},
 };
 
 pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_camera)
-            .add_systems(OnEnter(AppState::Game), add_chunk_loader_to_camera);
+        app.init_resource::<CameraZoomSettings>()
+            .register_input_action(
+                InputAction::ZoomIn,
+                [KeyCode::Equal, KeyCode::NumpadAdd].map(InputBinding::key),
+            )
+            .register_input_action(
+                InputAction::ZoomOut,
+                [KeyCode::Minus, KeyCode::NumpadSubtract].map(InputBinding::key),
+            )
+            .add_observer(zoom_main_camera)
+            .add_systems(Startup, spawn_camera)
+            .add_systems(OnEnter(AppState::Game), add_chunk_loader_to_camera)
+            .add_systems(
+                Update,
+                (
+                    (
+                        zoom_with_mouse_wheel.run_if(view_input_allowed),
+                        zoom_with_actions,
+                    )
+                        .in_set(AppUpdate::PreData),
+                    scale_chunk_loader_with_zoom.in_set(AppUpdate::PostAction),
+                ),
+            );
     }
 }
 
@@ -17,6 +44,50 @@ impl Plugin for CameraPlugin {
 #[require(Camera2d)]
 pub struct MainCamera;
 
+impl MainCamera {
+    ///Smallest ChunkLoader range no matter how far in the camera is zoomed
+    pub const MIN_LOADER_RANGE: IVec2 = ivec2(2, 2);
+
+    ///World units per pixel
+    pub fn scale(projection: &Projection) -> f32 {
+        match projection {
+            Projection::Orthographic(ortho) => ortho.scale,
+            _ => 1.0,
+        }
+    }
+
+    ///ChunkLoader range that covers a view half_size big from any point in a chunk
+    pub fn loader_range(half_size: Vec2) -> IVec2 {
+        ((half_size / Chunk::SIZE).ceil().as_ivec2() + IVec2::ONE).max(Self::MIN_LOADER_RANGE)
+    }
+}
+
+#[derive(Resource)]
+pub struct CameraZoomSettings {
+    pub min_scale: f32,
+    pub max_scale: f32,
+    ///How much one line of the mouse wheel zooms by
+    pub wheel_step: f32,
+    ///How much holding a zoom key zooms by each second
+    pub key_speed: f32,
+}
+
+impl Default for CameraZoomSettings {
+    fn default() -> Self {
+        Self {
+            min_scale: 0.5,
+            max_scale: 3.0,
+            wheel_step: 0.1,
+            key_speed: 2.0,
+        }
+    }
+}
+
+///Multiplies the scale of the MainCamera keeping the point under the cursor in place
+///Above 1.0 zooms out
+#[derive(Event)]
+pub struct ZoomMainCamera(pub f32);
+
 fn spawn_camera(mut commands: Commands) {
     commands.spawn((MainCamera,));
 }
@@ -27,3 +98,107 @@ fn add_chunk_loader_to_camera(mut camera: Query<Entity, With<MainCamera>>, mut c
         .expect("Getting only one MainCamera faild");
     commands.entity(camera).insert(ChunkLoader(ivec3(2, 2, 0)));
 }
+
+fn zoom_with_mouse_wheel(
+    mut wheel: EventReader<MouseWheel>,
+    settings: Res<CameraZoomSettings>,
+    mut commands: Commands,
+) {
+    let lines: f32 = wheel
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            //Roughly how many pixels a line is
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        })
+        .sum();
+    if lines != 0.0 {
+        //Scrolling up zooms in
+        commands.trigger(ZoomMainCamera((1.0 - settings.wheel_step).powf(lines)));
+    }
+}
+
+fn zoom_with_actions(
+    time: Res<Time>,
+    actions: Res<ActionMap>,
+    settings: Res<CameraZoomSettings>,
+    mut commands: Commands,
+) {
+    let mut amount = 0.0;
+    if actions.pressed(InputAction::ZoomIn) {
+        amount -= 1.0;
+    }
+    if actions.pressed(InputAction::ZoomOut) {
+        amount += 1.0;
+    }
+    if amount != 0.0 {
+        let factor = settings.key_speed.powf(amount * time.delta_secs());
+        commands.trigger(ZoomMainCamera(factor));
+    }
+}
+
+fn zoom_main_camera(
+    trigger: Trigger<ZoomMainCamera>,
+    settings: Res<CameraZoomSettings>,
+    cursor: Res<CurrsorPositon>,
+    mut camera: Query<(&mut Projection, &GlobalTransform, &ChildOf), With<MainCamera>>,
+    following: Query<(), With<FollowTarget>>,
+    mut commands: Commands,
+) {
+    let Ok((mut projection, transform, view)) = camera.single_mut() else {
+        return;
+    };
+    let Projection::Orthographic(ortho) = &mut *projection else {
+        warn!("MainCamera is not orthographic");
+        return;
+    };
+    let scale = (ortho.scale * trigger.0).clamp(settings.min_scale, settings.max_scale);
+    let factor = scale / ortho.scale;
+    if factor == 1.0 {
+        return;
+    }
+    ortho.scale = scale;
+    //Shifting the view would stop it following
+    if following.contains(view.parent()) {
+        return;
+    }
+    let pos = transform.translation().xy();
+    commands.trigger(MoveActivePlayerView::By((**cursor - pos) * (1.0 - factor)));
+}
+
+///Also runs when the loader is added so the first range fits the starting zoom
+fn scale_chunk_loader_with_zoom(
+    mut camera: Query<
+        (&Projection, &mut ChunkLoader),
+        (
+            With<MainCamera>,
+            Or<(Changed<Projection>, Added<ChunkLoader>)>,
+        ),
+    >,
+) {
+    let Ok((projection, mut loader)) = camera.single_mut() else {
+        return;
+    };
+    let Projection::Orthographic(ortho) = projection else {
+        return;
+    };
+    let range = MainCamera::loader_range(ortho.area.half_size());
+    if range != loader.0.xy() {
+        loader.0 = range.extend(loader.0.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loader_range() {
+        assert_eq!(
+            MainCamera::loader_range(Vec2::ZERO),
+            MainCamera::MIN_LOADER_RANGE
+        );
+        assert_eq!(MainCamera::loader_range(vec2(640.0, 360.0)), ivec2(3, 2));
+        assert_eq!(MainCamera::loader_range(vec2(1920.0, 1080.0)), ivec2(5, 4));
+    }
+}
@@ -19,7 +19,12 @@ impl Plugin for ChunkPlugin {
 
         app.add_systems(
             Update,
-            load_chunks_around_chunk_loader.in_set(AppUpdate::Action),
+            (
+                unload_chunks_outside_chunk_loaders,
+                load_chunks_around_chunk_loader,
+            )
+                .chain()
+                .in_set(AppUpdate::Action),
         );
 
         #[cfg(feature = "chunk_info")]
@@ -68,9 +73,9 @@ impl Chunk {
         Self::get_chunk_pos(pos)
     }
     pub fn get_chunk_pos(pos: Vec3) -> IVec3 {
-        let Vec2 { x, y } = pos.xy() / Self::SIZE;
-
-        ivec3(x as i32, y as i32, pos.z as i32)
+        //Floored so the chunks left of and below the origin don't share chunk 0
+        let chunk = (pos.xy() / Self::SIZE).floor().as_ivec2();
+        chunk.extend(pos.z as i32)
     }
 }
 
@@ -84,6 +89,7 @@ fn on_add_chunk(mut world: DeferredWorld, HookContext { entity, .. }: HookContex
 }
 
 ///Removes Chunk from ChunkManager
+///The tiles are children of the chunk so they get despawned along with it
 fn on_remove_chunk(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
     let chunk_pos = world.get::<ChunkPos>(entity).unwrap().0;
     world
@@ -190,6 +196,17 @@ impl<'a, 'b, B: QueryData, C: QueryFilter> ChunkGrabberMut<'a, 'b, B, C> {
 #[derive(Component, Default)]
 #[require(Transform)]
 pub struct ChunkLoader(pub IVec3);
+impl ChunkLoader {
+    ///How many chunks past the load range a chunk can be before it gets unloaded
+    ///Stops chunks on the edge from flickering in and out as the loader moves
+    pub const UNLOAD_MARGIN: IVec2 = ivec2(1, 1);
+
+    ///Checks if a loader at loader_pos should keep the chunk at chunk_pos loaded
+    pub fn keeps_loaded(&self, loader_pos: IVec2, chunk_pos: IVec3) -> bool {
+        let range = self.0.xy() + Self::UNLOAD_MARGIN;
+        chunk_pos.z == self.0.z && (chunk_pos.xy() - loader_pos).abs().cmple(range).all()
+    }
+}
 
 #[derive(Component, Default)]
 pub struct KeepChunkLoaded;
@@ -218,6 +235,26 @@ fn load_chunks_around_chunk_loader(
     }
 }
 
+fn unload_chunks_outside_chunk_loaders(
+    chunk_loaders: Query<(&ChunkLoader, &GlobalTransform)>,
+    chunks: Query<(Entity, &ChunkPos), (With<Chunk>, Without<KeepChunkLoaded>)>,
+    mut commands: Commands,
+) {
+    let loaders: Vec<(&ChunkLoader, IVec2)> = chunk_loaders
+        .iter()
+        .map(|(loader, transform)| (loader, Chunk::g_transform_to_chunk_pos(transform).xy()))
+        .collect();
+    for (chunk_id, chunk_pos) in chunks.iter() {
+        let in_range = loaders
+            .iter()
+            .any(|(loader, loader_pos)| loader.keeps_loaded(*loader_pos, **chunk_pos));
+        if !in_range {
+            //on_remove_chunk takes it out of the ChunkManager
+            commands.entity(chunk_id).despawn();
+        }
+    }
+}
+
 //returns an iter of all points in outer but not in inner
 fn _shell_range(outer: IVec2, inner: IVec2, center_pos: IVec2) -> impl Iterator<Item = IVec2> {
     let outer_max = outer + center_pos;
@@ -279,6 +316,43 @@ pub fn update_terrain_view_level(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_chunk_loader_keeps_loaded() {
+        let loader = ChunkLoader(ivec3(2, 2, 0));
+        let loader_pos = ivec2(5, -3);
+        //inside the load range
+        assert!(loader.keeps_loaded(loader_pos, ivec3(5, -3, 0)));
+        assert!(loader.keeps_loaded(loader_pos, ivec3(7, -1, 0)));
+        //inside the unload margin
+        assert!(loader.keeps_loaded(loader_pos, ivec3(8, -6, 0)));
+        assert!(loader.keeps_loaded(loader_pos, ivec3(2, 0, 0)));
+        //outside the unload margin
+        assert!(!loader.keeps_loaded(loader_pos, ivec3(9, -3, 0)));
+        assert!(!loader.keeps_loaded(loader_pos, ivec3(5, -7, 0)));
+        //other layer
+        assert!(!loader.keeps_loaded(loader_pos, ivec3(5, -3, 1)));
+    }
+
+    #[test]
+    fn test_get_chunk_pos_negative() {
+        assert_eq!(
+            Chunk::get_chunk_pos(vec3(250.0, 750.0, 0.0)),
+            ivec3(0, 1, 0)
+        );
+        assert_eq!(
+            Chunk::get_chunk_pos(vec3(-1.0, -499.0, 1.0)),
+            ivec3(-1, -1, 1)
+        );
+        assert_eq!(
+            Chunk::get_chunk_pos(vec3(-500.0, 0.0, 0.0)),
+            ivec3(-1, 0, 0)
+        );
+        assert_eq!(
+            Chunk::get_chunk_pos(vec3(-501.0, 0.0, 0.0)),
+            ivec3(-2, 0, 0)
+        );
+    }
+
     #[test]
     fn test_basic_shell_range() {
         let result: Vec<IVec2> = _shell_range(ivec2(2, 2), ivec2(1, 1), ivec2(0, 0)).collect();
@@ -1,6 +1,7 @@
 use crate::app::AppUpdate;
 use crate::camera::MainCamera;
 use crate::chunk::{Chunk, ChunkLoader};
+use crate::input::{VirtualCursor, replaying_input};
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
@@ -10,7 +11,10 @@ impl Plugin for CurrsorPlugin {
         app.init_resource::<CurrsorPositon>()
             .add_systems(
                 Update,
-                (update_currsor_pos, update_transform_cursor_entity)
+                (
+                    update_currsor_pos.run_if(not(replaying_input)),
+                    update_transform_cursor_entity,
+                )
                     .chain()
                     .in_set(AppUpdate::PreData),
             )
@@ -25,13 +29,18 @@ fn update_currsor_pos(
     mut currsor_positon: ResMut<CurrsorPositon>,
     window: Query<&Window, With<PrimaryWindow>>,
     camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    virtual_cursor: Res<VirtualCursor>,
 ) {
     let (camera, camera_transform) = camera.single().unwrap();
 
     let window = window.single().unwrap();
 
-    if let Some(world_position) = window
-        .cursor_position()
+    let cursor_position = if virtual_cursor.active {
+        Some(virtual_cursor.pos)
+    } else {
+        window.cursor_position()
+    };
+    if let Some(world_position) = cursor_position
         .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor).ok())
         .map(|ray| ray.origin.truncate())
     {
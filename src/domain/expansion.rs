@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::Domains;
+use crate::{
+    app::AppUpdate,
+    game::PlayState,
+    player::{OwnedBy, PlayerId, core::PlayerCore},
+    terrain::{TileGrid, TileType, global_tile_at, global_tile_center},
+};
+
+pub struct DomainExpansionPlugin;
+impl Plugin for DomainExpansionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExpansionSettings>()
+            .add_observer(add_territory_to_core)
+            .add_systems(
+                Update,
+                grow_territory
+                    .run_if(in_state(PlayState::Playing))
+                    .in_set(AppUpdate::Action),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct ExpansionSettings {
+    ///Time between each ring of growth
+    pub growth_interval: Duration,
+    ///Furthest a territory can grow from its core in tiles
+    pub max_radius: u32,
+}
+
+impl Default for ExpansionSettings {
+    fn default() -> Self {
+        Self {
+            growth_interval: Duration::from_millis(500),
+            max_radius: 12,
+        }
+    }
+}
+
+///The area a PlayerCore has grown into
+#[derive(Component)]
+pub struct Territory {
+    ///Global tile of the core
+    pub origin: IVec2,
+    pub layer: i32,
+    ///Owned tiles that still have neighbours they could grow into
+    frontier: Vec<IVec2>,
+    timer: Timer,
+}
+
+impl Territory {
+    pub fn new(origin: IVec2, layer: i32, growth_interval: Duration) -> Self {
+        Self {
+            origin,
+            layer,
+            frontier: Vec::new(),
+            timer: Timer::new(growth_interval, TimerMode::Repeating),
+        }
+    }
+
+    pub fn in_range(&self, tile: IVec2, max_radius: u32) -> bool {
+        let max_radius = max_radius as i32;
+        (tile - self.origin).length_squared() <= max_radius * max_radius
+    }
+}
+
+fn add_territory_to_core(
+    trigger: Trigger<OnAdd, PlayerCore>,
+    cores: Query<&Transform>,
+    settings: Res<ExpansionSettings>,
+    mut commands: Commands,
+) {
+    let Ok(transform) = cores.get(trigger.target()) else {
+        return;
+    };
+    let origin = global_tile_at(transform.translation.xy());
+    //The core brakes its tiles on layer 0
+    commands
+        .entity(trigger.target())
+        .insert(Territory::new(origin, 0, settings.growth_interval));
+}
+
+fn grow_territory(
+    time: Res<Time>,
+    settings: Res<ExpansionSettings>,
+    mut cores: Query<(&mut Territory, &OwnedBy), With<PlayerCore>>,
+    players: Query<&PlayerId>,
+    tiles: TileGrid,
+    mut domains: Domains,
+) {
+    for (mut territory, &OwnedBy(owner)) in cores.iter_mut() {
+        if !territory.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let Ok(&player) = players.get(owner) else {
+            continue;
+        };
+        let layer = territory.layer;
+
+        //Start from the core
+        if territory.frontier.is_empty() {
+            let origin = global_tile_center(territory.origin);
+            if domains.owner_at(origin, layer) == Some(None) {
+                domains.claim(origin, layer, player);
+                let origin = territory.origin;
+                territory.frontier.push(origin);
+            }
+            continue;
+        }
+
+        let frontier = std::mem::take(&mut territory.frontier);
+        for tile in frontier {
+            //Stays in the frontier while it touches something that might open up later
+            let mut blocked = false;
+            for neighbour in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y].map(|d| tile + d) {
+                if !territory.in_range(neighbour, settings.max_radius) {
+                    continue;
+                }
+                let pos = global_tile_center(neighbour);
+                match domains.owner_at(pos, layer) {
+                    //Already ours or an enemy border
+                    Some(Some(_)) => continue,
+                    Some(None) => {}
+                    //Chunk not loaded
+                    None => {
+                        blocked = true;
+                        continue;
+                    }
+                }
+                match tiles.tile_type_at(neighbour, layer) {
+                    Some(TileType::Ground) => {
+                        domains.claim(pos, layer, player);
+                        territory.frontier.push(neighbour);
+                    }
+                    //A wall or a chunk still generating
+                    _ => blocked = true,
+                }
+            }
+            if blocked {
+                territory.frontier.push(tile);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_territory_in_range() {
+        let territory = Territory::new(ivec2(-3, 4), 0, Duration::ZERO);
+        assert!(territory.in_range(ivec2(-3, 4), 0));
+        assert!(territory.in_range(ivec2(0, 8), 5));
+        assert!(!territory.in_range(ivec2(1, 8), 5));
+        assert!(!territory.in_range(ivec2(-3, 5), 0));
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    chunk::{Chunk, ChunkManager, ChunkPos},
+    player::PlayerId,
+};
+
+mod expansion;
+mod overlay;
+
+pub struct DomainPlugin;
+impl Plugin for DomainPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DomainChanged>()
+            .init_resource::<UnloadedDomains>()
+            .add_observer(add_domain_to_chunk)
+            .add_observer(keep_domain_of_unloaded_chunk)
+            .add_plugins((
+                expansion::DomainExpansionPlugin,
+                overlay::DomainOverlayPlugin,
+            ));
+    }
+}
+
+///Domains of unloaded chunks that have owned nodes
+///Ownership lives here while the chunk is gone so it isn't lost when the chunk unloads
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct UnloadedDomains(HashMap<IVec3, Domain>);
+
+///Gives the chunk back its Domain if it was unloaded before
+fn add_domain_to_chunk(
+    trigger: Trigger<OnAdd, Chunk>,
+    chunks: Query<&ChunkPos>,
+    mut unloaded: ResMut<UnloadedDomains>,
+    mut changes: EventWriter<DomainChanged>,
+    mut commands: Commands,
+) {
+    let chunk = trigger.target();
+    let domain = chunks
+        .get(chunk)
+        .ok()
+        .and_then(|chunk_pos| unloaded.remove(&**chunk_pos))
+        .unwrap_or_default();
+    //Tell everyone about the restored owners like they were just claimed
+    for (node, owner) in domain.owned_nodes() {
+        changes.write(DomainChanged {
+            chunk,
+            node,
+            owner: Some(owner),
+        });
+    }
+    commands.entity(chunk).insert(domain);
+}
+
+fn keep_domain_of_unloaded_chunk(
+    trigger: Trigger<OnRemove, Chunk>,
+    chunks: Query<(&ChunkPos, &Domain)>,
+    mut unloaded: ResMut<UnloadedDomains>,
+) {
+    let Ok((chunk_pos, domain)) = chunks.get(trigger.target()) else {
+        return;
+    };
+    if domain.owned_nodes().next().is_some() {
+        unloaded.insert(**chunk_pos, domain.clone());
+    }
+}
+
+#[derive(Component, Clone)]
+pub struct Domain([DomainNode; Self::COUNT.width * Self::COUNT.height]);
+
+impl Default for Domain {
+    fn default() -> Self {
+        Self([DomainNode::default(); Self::COUNT.width * Self::COUNT.height])
+    }
+}
+
+impl Domain {
+    pub const COUNT: DomainCount = DomainCount::new(10, 10);
+    pub const SIZE: Vec2 = vec2(
+        Chunk::SIZE.x / Self::COUNT.width as f32,
+        Chunk::SIZE.y / Self::COUNT.height as f32,
+    );
+    pub const fn index(x: usize, y: usize) -> usize {
+        y * Self::COUNT.width + x
+    }
+
+    ///Gets the node in its chunk at the global translation
+    pub fn node_at(pos: Vec2) -> UVec2 {
+        let max = uvec2(Self::COUNT.width as u32 - 1, Self::COUNT.height as u32 - 1);
+        //rem_euclid can round up to SIZE for tiny negative values so clamp it
+        (pos.rem_euclid(Chunk::SIZE) / Self::SIZE)
+            .as_uvec2()
+            .min(max)
+    }
+
+    ///Translation of the center of the node relative to its chunk
+    pub fn node_center(node: UVec2) -> Vec2 {
+        (node.as_vec2() + 0.5) * Self::SIZE
+    }
+
+    pub fn get(&self, node: UVec2) -> &DomainNode {
+        &self.0[Self::index(node.x as usize, node.y as usize)]
+    }
+
+    pub fn get_mut(&mut self, node: UVec2) -> &mut DomainNode {
+        &mut self.0[Self::index(node.x as usize, node.y as usize)]
+    }
+
+    ///Every node that has an owner
+    pub fn owned_nodes(&self) -> impl Iterator<Item = (UVec2, PlayerId)> + '_ {
+        self.0.iter().enumerate().filter_map(|(index, node)| {
+            let node_pos = uvec2(
+                (index % Self::COUNT.width) as u32,
+                (index / Self::COUNT.width) as u32,
+            );
+            Some((node_pos, node.owner()?))
+        })
+    }
+}
+
+pub struct DomainCount {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl DomainCount {
+    pub const fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+}
+
+#[derive(Component, Default, Clone, Copy)]
+pub struct DomainNode {
+    //PlayerId
+    owner: Option<u8>,
+}
+
+impl DomainNode {
+    pub fn owner(&self) -> Option<PlayerId> {
+        self.owner.map(PlayerId)
+    }
+}
+
+///Sent when a DomainNode changes owner
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DomainChanged {
+    pub chunk: Entity,
+    pub node: UVec2,
+    pub owner: Option<PlayerId>,
+}
+
+///Reads and claims DomainNodes by global translation
+#[derive(SystemParam)]
+pub struct Domains<'w, 's> {
+    chunk_manager: Res<'w, ChunkManager>,
+    domains: Query<'w, 's, &'static mut Domain>,
+    changes: EventWriter<'w, DomainChanged>,
+}
+
+impl Domains<'_, '_> {
+    ///Returns None if there is no loaded chunk at pos
+    pub fn owner_at(&self, pos: Vec2, layer: i32) -> Option<Option<PlayerId>> {
+        let chunk_id = self.chunk_manager.get_chunk_at(&pos, layer)?;
+        let domain = self.domains.get(chunk_id).ok()?;
+        Some(domain.get(Domain::node_at(pos)).owner())
+    }
+
+    ///Gives the node at pos to player
+    ///Returns false if there is no loaded chunk at pos
+    pub fn claim(&mut self, pos: Vec2, layer: i32, player: PlayerId) -> bool {
+        let Some(chunk_id) = self.chunk_manager.get_chunk_at(&pos, layer) else {
+            return false;
+        };
+        let Ok(mut domain) = self.domains.get_mut(chunk_id) else {
+            return false;
+        };
+        let node = Domain::node_at(pos);
+        let owner = Some(player);
+        if domain.get(node).owner() != owner {
+            domain.get_mut(node).owner = Some(player.0);
+            self.changes.write(DomainChanged {
+                chunk: chunk_id,
+                node,
+                owner,
+            });
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn test_node_at() {
+        assert_eq!(Domain::node_at(vec2(0.0, 0.0)), uvec2(0, 0));
+        assert_eq!(Domain::node_at(vec2(49.9, 50.0)), uvec2(0, 1));
+        assert_eq!(Domain::node_at(vec2(499.0, 510.0)), uvec2(9, 0));
+        assert_eq!(Domain::node_at(vec2(-1.0, -499.0)), uvec2(9, 0));
+        assert_eq!(Domain::node_at(vec2(-500.0, -0.0001)), uvec2(0, 9));
+    }
+
+    #[test]
+    fn test_claim_sends_change() {
+        let mut app = App::new();
+        app.init_resource::<ChunkManager>()
+            .init_resource::<UnloadedDomains>()
+            .add_event::<DomainChanged>()
+            .add_observer(add_domain_to_chunk);
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(ivec3(-1, 0, 0))))
+            .id();
+        app.update();
+
+        let pos = vec2(-120.0, 260.0);
+        let claim = move |mut domains: Domains| {
+            assert!(domains.claim(pos, 0, PlayerId(3)));
+            //Claiming again is not a change
+            assert!(domains.claim(pos, 0, PlayerId(3)));
+            assert!(!domains.claim(pos, 1, PlayerId(3)));
+            assert_eq!(domains.owner_at(pos, 0), Some(Some(PlayerId(3))));
+        };
+        app.world_mut().run_system_once(claim).unwrap();
+
+        let changes: Vec<DomainChanged> = app
+            .world_mut()
+            .resource_mut::<Events<DomainChanged>>()
+            .drain()
+            .collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].chunk, chunk);
+        assert_eq!(changes[0].node, uvec2(7, 5));
+        assert_eq!(changes[0].owner, Some(PlayerId(3)));
+    }
+
+    #[test]
+    fn test_domain_survives_unload() {
+        let mut app = App::new();
+        app.init_resource::<ChunkManager>()
+            .init_resource::<UnloadedDomains>()
+            .add_event::<DomainChanged>()
+            .add_observer(add_domain_to_chunk)
+            .add_observer(keep_domain_of_unloaded_chunk);
+        let chunk_pos = ivec3(2, -1, 0);
+        let chunk = app.world_mut().spawn((Chunk, ChunkPos(chunk_pos))).id();
+        app.update();
+
+        let pos = vec2(1020.0, -30.0);
+        let claim = move |mut domains: Domains| assert!(domains.claim(pos, 0, PlayerId(1)));
+        app.world_mut().run_system_once(claim).unwrap();
+        app.world_mut().despawn(chunk);
+        app.world_mut()
+            .resource_mut::<Events<DomainChanged>>()
+            .clear();
+
+        let chunk = app.world_mut().spawn((Chunk, ChunkPos(chunk_pos))).id();
+        app.update();
+        let owner = move |domains: Domains| domains.owner_at(pos, 0);
+        let owner = app.world_mut().run_system_once(owner).unwrap();
+        assert_eq!(owner, Some(Some(PlayerId(1))));
+        assert!(app.world().resource::<UnloadedDomains>().is_empty());
+
+        let changes: Vec<DomainChanged> = app
+            .world_mut()
+            .resource_mut::<Events<DomainChanged>>()
+            .drain()
+            .collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].chunk, chunk);
+        assert_eq!(changes[0].node, Domain::node_at(pos));
+    }
+}
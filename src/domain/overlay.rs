@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use super::{Domain, DomainChanged};
+use crate::{
+    app::AppUpdate,
+    chunk::{ChunkManager, ChunkPos},
+    terrain::DOMAIN_TINT_Z,
+};
+
+pub struct DomainOverlayPlugin;
+impl Plugin for DomainOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<ShowDomainOverlay>()
+            .add_observer(add_domain_tints_to_domain)
+            .add_systems(Update, update_domain_tints.in_set(AppUpdate::PostAction))
+            .add_systems(
+                Update,
+                draw_domain_borders.run_if(in_state(ShowDomainOverlay::Yes)),
+            )
+            .add_systems(OnEnter(ShowDomainOverlay::Yes), show_domain_tints)
+            .add_systems(OnEnter(ShowDomainOverlay::No), hide_domain_tints);
+    }
+}
+
+#[derive(States, Default, Clone, Eq, PartialEq, Hash, Debug, Copy)]
+pub enum ShowDomainOverlay {
+    #[default]
+    Yes,
+    No,
+}
+
+impl ShowDomainOverlay {
+    fn visibility(&self) -> Visibility {
+        match self {
+            ShowDomainOverlay::Yes => Visibility::Inherited,
+            ShowDomainOverlay::No => Visibility::Hidden,
+        }
+    }
+}
+
+///Sprite tinting an owned DomainNode, child of its Chunk
+#[derive(Component)]
+pub struct DomainTint;
+
+///The DomainTint of each owned node in a Chunk
+#[derive(Component, Default, Deref, DerefMut)]
+pub struct DomainTints(HashMap<UVec2, Entity>);
+
+impl DomainTints {
+    const ALPHA: f32 = 0.3;
+}
+
+fn add_domain_tints_to_domain(trigger: Trigger<OnAdd, Domain>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(DomainTints::default());
+}
+
+fn update_domain_tints(
+    mut changes: EventReader<DomainChanged>,
+    mut chunks: Query<&mut DomainTints>,
+    overlay: Res<State<ShowDomainOverlay>>,
+    mut commands: Commands,
+) {
+    for change in changes.read() {
+        let Ok(mut tints) = chunks.get_mut(change.chunk) else {
+            continue;
+        };
+        if let Some(tint) = tints.remove(&change.node) {
+            commands.entity(tint).despawn();
+        }
+        if let Some(owner) = change.owner {
+            let tint = commands
+                .spawn((
+                    DomainTint,
+                    Sprite {
+                        color: owner.color().with_alpha(DomainTints::ALPHA),
+                        custom_size: Some(Domain::SIZE),
+                        ..default()
+                    },
+                    Transform::from_translation(
+                        Domain::node_center(change.node).extend(DOMAIN_TINT_Z),
+                    ),
+                    overlay.visibility(),
+                    ChildOf(change.chunk),
+                ))
+                .id();
+            tints.insert(change.node, tint);
+        }
+    }
+}
+
+fn show_domain_tints(mut tints: Query<&mut Visibility, With<DomainTint>>) {
+    for mut visibility in tints.iter_mut() {
+        *visibility = ShowDomainOverlay::Yes.visibility();
+    }
+}
+
+fn hide_domain_tints(mut tints: Query<&mut Visibility, With<DomainTint>>) {
+    for mut visibility in tints.iter_mut() {
+        *visibility = ShowDomainOverlay::No.visibility();
+    }
+}
+
+///Draws a line on every edge between nodes with different owners
+fn draw_domain_borders(
+    chunks: Query<(&ChunkPos, &Domain)>,
+    chunk_manager: Res<ChunkManager>,
+    mut gizmos: Gizmos,
+) {
+    let owner_at = |pos: Vec2, layer: i32| {
+        let chunk_id = chunk_manager.get_chunk_at(&pos, layer)?;
+        let (_, domain) = chunks.get(chunk_id).ok()?;
+        domain.get(Domain::node_at(pos)).owner()
+    };
+    let half = Domain::SIZE / 2.0;
+    //Direction to the neighbour and the two corners of the shared edge
+    let edges = [
+        (Vec2::X, vec2(half.x, -half.y), half),
+        (Vec2::NEG_X, -half, vec2(-half.x, half.y)),
+        (Vec2::Y, vec2(-half.x, half.y), half),
+        (Vec2::NEG_Y, -half, vec2(half.x, -half.y)),
+    ];
+
+    for (chunk_pos, domain) in chunks.iter() {
+        let origin = chunk_pos.into_vec3();
+        for x in 0..Domain::COUNT.width as u32 {
+            for y in 0..Domain::COUNT.height as u32 {
+                let node = uvec2(x, y);
+                let Some(owner) = domain.get(node).owner() else {
+                    continue;
+                };
+                let center = origin.xy() + Domain::node_center(node);
+                for (direction, start, end) in edges {
+                    let neighbour = center + direction * Domain::SIZE;
+                    if owner_at(neighbour, chunk_pos.z) == Some(owner) {
+                        continue;
+                    }
+                    gizmos.line(
+                        (center + start).extend(origin.z),
+                        (center + end).extend(origin.z),
+                        owner.color(),
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,23 @@
 use bevy::prelude::*;
 
-use crate::app::AppState;
+use crate::{
+    app::{AppState, AppUpdate},
+    input::{ActionMap, InputAction, InputActionAppExt, InputBinding},
+};
 
 pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_sub_state::<PlayState>();
+        app.add_sub_state::<PlayState>()
+            //Same key both ways, they live in different InputContexts
+            .register_input_action(InputAction::Pause, [InputBinding::key(KeyCode::Escape)])
+            .register_input_action(InputAction::Resume, [InputBinding::key(KeyCode::Escape)])
+            .add_systems(
+                Update,
+                toggle_pause
+                    .run_if(in_state(AppState::Game))
+                    .in_set(AppUpdate::PreData),
+            );
     }
 }
 
@@ -16,3 +28,11 @@ pub enum PlayState {
     #[default]
     Playing,
 }
+
+fn toggle_pause(actions: Res<ActionMap>, mut next: ResMut<NextState<PlayState>>) {
+    if actions.just_pressed(InputAction::Pause) {
+        next.set(PlayState::Paused);
+    } else if actions.just_pressed(InputAction::Resume) {
+        next.set(PlayState::Playing);
+    }
+}
@@ -3,11 +3,17 @@ use bevy::prelude::*;
 pub struct HelperPlugin;
 impl Plugin for HelperPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(move_entity_to::MoveEntityToPlugin);
+        app.add_plugins((
+            move_entity_to::MoveEntityToPlugin,
+            pathfinding::PathfindingPlugin,
+            steering::SteeringPlugin,
+        ));
     }
 }
 
 pub mod move_entity_to;
+pub mod pathfinding;
+pub mod steering;
 
 mod create_texture_atlas;
 pub use create_texture_atlas::create_texture_atlas;
@@ -1,21 +1,77 @@
-use bevy::prelude::*;
+use std::{
+    collections::{HashSet, VecDeque},
+    time::Duration,
+};
 
-use crate::app::AppUpdate;
+use bevy::{
+    ecs::{
+        component::{ComponentId, HookContext},
+        query::QueryFilter,
+        world::DeferredWorld,
+    },
+    prelude::*,
+};
+
+use crate::{app::AppUpdate, game::PlayState, helper::steering::Steering};
 
 pub struct MoveEntityToPlugin;
 impl Plugin for MoveEntityToPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, move_entity.in_set(AppUpdate::Action));
+        app.add_event::<MoveFinished>()
+            .init_resource::<ReplacingMoves>()
+            .add_observer(halt_movement)
+            .add_systems(
+                Update,
+                (
+                    (move_entity::<Without<MovesWhilePaused>>, follow_path)
+                        .run_if(in_state(PlayState::Playing)),
+                    move_entity::<With<MovesWhilePaused>>,
+                )
+                    .in_set(AppUpdate::Action),
+            );
     }
 }
 
+/// Marks an entity whose `MoveEntityTo` keeps going while the game is paused
+#[derive(Component, Default)]
+pub struct MovesWhilePaused;
+
 /// Component that moves an entity from `from` to `to` using an easing curve
 #[derive(Component)]
 #[require(Speed)]
+#[component(
+    on_replace = on_replace_move,
+    on_insert = on_insert_move,
+    on_remove = on_remove_move,
+)]
 pub struct MoveEntityTo {
     pub to: Vec2,
     pub from: Vec2,
     pub easing: EaseFunction,
+    pub mode: MoveMode,
+}
+
+impl MoveEntityTo {
+    /// Position along the easing curve from `from` to `to` at `t` from 0.0 to 1.0
+    pub fn sample(&self, t: f32) -> Vec2 {
+        self.from.lerp(self.to, self.easing.sample_clamped(t))
+    }
+}
+
+/// How a `MoveEntityTo` gets from `from` to `to`
+#[derive(Clone, Debug, Default)]
+pub enum MoveMode {
+    /// Moves at `Speed` with the easing scaling how fast it goes
+    #[default]
+    Speed,
+    /// Takes exactly the timer's duration with the position sampled from the easing curve
+    Timed(Timer),
+}
+
+impl MoveMode {
+    pub fn timed(duration: Duration) -> Self {
+        Self::Timed(Timer::new(duration, TimerMode::Once))
+    }
 }
 
 #[derive(Component, Deref, DerefMut)]
@@ -29,54 +85,65 @@ impl Default for Speed {
     }
 }
 
-fn move_entity(
+fn move_entity<F: QueryFilter>(
     time: Res<Time>,
     mut commands: Commands,
-    mut query: Query<(
-        Entity,
-        &mut Transform,
-        &GlobalTransform,
-        &MoveEntityTo,
-        &Speed,
-    )>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &GlobalTransform,
+            &mut MoveEntityTo,
+            &Speed,
+            Option<&Steering>,
+        ),
+        F,
+    >,
 ) {
-    for (entity, mut transform, g_transform, move_to, Speed(speed)) in query.iter_mut() {
+    for (entity, mut transform, g_transform, mut move_to, Speed(speed), steering) in
+        query.iter_mut()
+    {
+        if let MoveMode::Timed(timer) = &mut move_to.mode {
+            timer.tick(time.delta());
+            let (t, finished) = (timer.fraction(), timer.finished());
+            transform.translation = move_to.sample(t).extend(transform.translation.z);
+            if finished {
+                commands.entity(entity).remove::<MoveEntityTo>();
+                finish_move(&mut commands, entity, MoveFinishReason::Arrived);
+            }
+            continue;
+        }
         let current_pos = g_transform.translation().xy();
-        let MoveEntityTo { to, from, easing } = *move_to;
+        let MoveEntityTo {
+            to, from, easing, ..
+        } = *move_to;
         let total_distance = from.distance(to);
         // Handle edge case where from == to
         if total_distance <= f32::EPSILON {
             transform.translation = to.extend(transform.translation.z);
             commands.entity(entity).remove::<MoveEntityTo>();
+            finish_move(&mut commands, entity, MoveFinishReason::Arrived);
             continue;
         }
         let traveled_distance = from.distance(current_pos);
         let remaining_distance = current_pos.distance(to);
         // Calculate progress (0.0 to 1.0)
         let t = (traveled_distance / total_distance).min(1.0);
-        let Some(eased_t) = easing.sample(t) else {
-            warn!("easing sample({t}) failed");
+        let Some(easing_speed_factor) = easing_speed_factor(easing, t) else {
             continue;
         };
         // Calculate movement step
         let direction = (to - current_pos).normalize_or_zero();
         let base_move_distance = speed * time.delta_secs();
-
-        // Apply easing to movement speed
-        // Use the easing value directly, but ensure minimum movement
-        let easing_speed_factor = if t >= 1.0 {
-            1.0
-        } else {
-            // For most easing functions, we want some minimum speed even when eased_t is 0
-            // This prevents the entity from getting stuck at the start
-            (eased_t + 0.1).min(1.0)
-        };
-        let move_step = direction * base_move_distance * easing_speed_factor;
+        let arrival_factor =
+            steering.map_or(1.0, |steering| steering.arrival_factor(remaining_distance));
+        let move_step = direction * base_move_distance * easing_speed_factor * arrival_factor;
         // Check if we'll overshoot the target
         if remaining_distance <= move_step.length() {
             // Snap to target
             transform.translation = to.extend(transform.translation.z);
             commands.entity(entity).remove::<MoveEntityTo>();
+            finish_move(&mut commands, entity, MoveFinishReason::Arrived);
             //info!("Reached target for entity {:?}", entity);
         } else {
             // Apply movement
@@ -85,9 +152,206 @@ fn move_entity(
     }
 }
 
+// Apply easing to movement speed
+// Use the easing value directly, but ensure minimum movement
+fn easing_speed_factor(easing: EaseFunction, t: f32) -> Option<f32> {
+    if t >= 1.0 {
+        return Some(1.0);
+    }
+    let Some(eased_t) = easing.sample(t) else {
+        warn!("easing sample({t}) failed");
+        return None;
+    };
+    // For most easing functions, we want some minimum speed even when eased_t is 0
+    // This prevents the entity from getting stuck at the start
+    Some((eased_t + 0.1).min(1.0))
+}
+
+/// Component that moves an entity through `waypoints` in order
+/// The easing is applied over the whole path rather than each leg
+/// Should not be on the same entity as `MoveEntityTo`
+#[derive(Component)]
+#[require(Speed)]
+#[component(
+    on_replace = on_replace_move,
+    on_insert = on_insert_move,
+    on_remove = on_remove_move,
+)]
+pub struct FollowPath {
+    waypoints: VecDeque<Vec2>,
+    pub easing: EaseFunction,
+    length: f32,
+    traveled: f32,
+}
+
+impl FollowPath {
+    pub fn new(
+        from: Vec2,
+        waypoints: impl IntoIterator<Item = Vec2>,
+        easing: EaseFunction,
+    ) -> Self {
+        let waypoints: VecDeque<Vec2> = waypoints.into_iter().collect();
+        let mut length = 0.0;
+        let mut last = from;
+        for &waypoint in waypoints.iter() {
+            length += last.distance(waypoint);
+            last = waypoint;
+        }
+        Self {
+            waypoints,
+            easing,
+            length,
+            traveled: 0.0,
+        }
+    }
+
+    /// How far along the path from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.length <= f32::EPSILON {
+            return 1.0;
+        }
+        (self.traveled / self.length).min(1.0)
+    }
+
+    /// Moves `distance` along the path from `current` passing any waypoints reached
+    pub fn advance(&mut self, mut current: Vec2, mut distance: f32) -> Vec2 {
+        while let Some(&next) = self.waypoints.front() {
+            let remaining = current.distance(next);
+            if remaining > distance {
+                current += (next - current).normalize_or_zero() * distance;
+                self.traveled += distance;
+                break;
+            }
+            current = next;
+            distance -= remaining;
+            self.traveled += remaining;
+            self.waypoints.pop_front();
+        }
+        current
+    }
+}
+
+/// Why an entity stopped moving
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveFinishReason {
+    Arrived,
+    /// Stopped by `Halt`
+    Halted,
+    /// Another move of the same kind was inserted over it
+    Replaced,
+}
+
+/// Sent as an event and triggered on the entity when a `MoveEntityTo` or `FollowPath` ends
+/// Removing the component directly does not send it
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MoveFinished {
+    pub entity: Entity,
+    pub reason: MoveFinishReason,
+}
+
+fn finish_move(commands: &mut Commands, entity: Entity, reason: MoveFinishReason) {
+    let finished = MoveFinished { entity, reason };
+    commands.send_event(finished);
+    commands.trigger_targets(finished, entity);
+}
+
+/// Moves that are being replaced or removed
+/// on_replace runs before both so which one comes next tells them apart
+#[derive(Resource, Default)]
+struct ReplacingMoves(HashSet<(Entity, ComponentId)>);
+
+fn on_replace_move(
+    mut world: DeferredWorld,
+    HookContext {
+        entity,
+        component_id,
+        ..
+    }: HookContext,
+) {
+    if let Some(mut replacing) = world.get_resource_mut::<ReplacingMoves>() {
+        replacing.0.insert((entity, component_id));
+    }
+}
+
+fn on_insert_move(
+    mut world: DeferredWorld,
+    HookContext {
+        entity,
+        component_id,
+        ..
+    }: HookContext,
+) {
+    let replaced = world
+        .get_resource_mut::<ReplacingMoves>()
+        .is_some_and(|mut replacing| replacing.0.remove(&(entity, component_id)));
+    if replaced {
+        finish_move(&mut world.commands(), entity, MoveFinishReason::Replaced);
+    }
+}
+
+fn on_remove_move(
+    mut world: DeferredWorld,
+    HookContext {
+        entity,
+        component_id,
+        ..
+    }: HookContext,
+) {
+    if let Some(mut replacing) = world.get_resource_mut::<ReplacingMoves>() {
+        replacing.0.remove(&(entity, component_id));
+    }
+}
+
+fn follow_path(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut Transform,
+        &GlobalTransform,
+        &mut FollowPath,
+        &Speed,
+        Option<&Steering>,
+    )>,
+) {
+    for (entity, mut transform, g_transform, mut path, Speed(speed), steering) in query.iter_mut() {
+        let Some(easing_speed_factor) = easing_speed_factor(path.easing, path.progress()) else {
+            continue;
+        };
+        let arrival_factor = steering.map_or(1.0, |steering| {
+            steering.arrival_factor(path.length - path.traveled)
+        });
+        let current_pos = g_transform.translation().xy();
+        let distance = speed * time.delta_secs() * easing_speed_factor * arrival_factor;
+        let next_pos = path.advance(current_pos, distance);
+        transform.translation += (next_pos - current_pos).extend(0.0);
+        if path.waypoints.is_empty() {
+            commands.entity(entity).remove::<FollowPath>();
+            finish_move(&mut commands, entity, MoveFinishReason::Arrived);
+        }
+    }
+}
+
 #[derive(Component, Default)]
 pub struct Halt;
 
+fn halt_movement(
+    trigger: Trigger<OnAdd, Halt>,
+    query: Query<(Has<MoveEntityTo>, Has<FollowPath>)>,
+    mut commands: Commands,
+) {
+    let entity = trigger.target();
+    let Ok((moving, following)) = query.get(entity) else {
+        return;
+    };
+    if moving || following {
+        commands
+            .entity(entity)
+            .remove::<(MoveEntityTo, FollowPath)>();
+        finish_move(&mut commands, entity, MoveFinishReason::Halted);
+    }
+}
+
 pub fn on_halt<T>(trigger: Trigger<OnAdd, Halt>, mut commands: Commands, query: Query<&T>)
 where
     T: Component,
@@ -96,3 +360,240 @@ where
         commands.entity(trigger.target()).remove::<T>();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::AppState;
+
+    #[test]
+    fn test_follow_path_advance() {
+        let waypoints = [vec2(10.0, 0.0), vec2(10.0, 10.0), vec2(0.0, 10.0)];
+        let mut path = FollowPath::new(Vec2::ZERO, waypoints, EaseFunction::Linear);
+        assert_eq!(path.progress(), 0.0);
+
+        let pos = path.advance(Vec2::ZERO, 5.0);
+        assert_eq!(pos, vec2(5.0, 0.0));
+        assert_eq!(path.waypoints.len(), 3);
+
+        //Goes round the corner
+        let pos = path.advance(pos, 10.0);
+        assert_eq!(pos, vec2(10.0, 5.0));
+        assert_eq!(path.waypoints.len(), 2);
+        assert_eq!(path.progress(), 0.5);
+
+        //Never overshoots the end
+        let pos = path.advance(pos, 100.0);
+        assert_eq!(pos, vec2(0.0, 10.0));
+        assert!(path.waypoints.is_empty());
+        assert_eq!(path.progress(), 1.0);
+    }
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_event::<MoveFinished>()
+            .init_resource::<ReplacingMoves>()
+            .add_observer(halt_movement)
+            .add_systems(Update, (move_entity::<()>, follow_path));
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(std::time::Duration::from_secs(1));
+        app
+    }
+
+    fn finished(app: &App) -> Vec<MoveFinished> {
+        let events = app.world().resource::<Events<MoveFinished>>();
+        events.get_cursor().read(events).copied().collect()
+    }
+
+    fn move_to(to: Vec2) -> MoveEntityTo {
+        MoveEntityTo {
+            to,
+            from: Vec2::ZERO,
+            easing: EaseFunction::Linear,
+            mode: MoveMode::Speed,
+        }
+    }
+
+    #[test]
+    fn test_follow_path_reaches_end() {
+        let mut app = test_app();
+        let entity = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GlobalTransform::default(),
+                Speed(1000.0),
+                FollowPath::new(
+                    Vec2::ZERO,
+                    [vec2(30.0, 0.0), vec2(30.0, 40.0)],
+                    EaseFunction::Linear,
+                ),
+            ))
+            .id();
+        app.update();
+
+        let world = app.world();
+        assert!(world.get::<FollowPath>(entity).is_none());
+        assert_eq!(
+            world.get::<Transform>(entity).unwrap().translation,
+            vec3(30.0, 40.0, 0.0)
+        );
+        let finished = finished(&app);
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].entity, entity);
+        assert_eq!(finished[0].reason, MoveFinishReason::Arrived);
+    }
+
+    #[test]
+    fn test_move_finished_reasons() {
+        let mut app = test_app();
+        let entity = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GlobalTransform::default(),
+                move_to(vec2(1000.0, 0.0)),
+            ))
+            .id();
+
+        //Changing the target in place is not a new move
+        app.world_mut().get_mut::<MoveEntityTo>(entity).unwrap().to = vec2(0.0, 1000.0);
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(move_to(vec2(2000.0, 0.0)));
+        app.world_mut().entity_mut(entity).insert(Halt);
+        //Removing it directly is silent
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(move_to(vec2(3000.0, 0.0)))
+            .remove::<MoveEntityTo>();
+        app.update();
+
+        let reasons: Vec<MoveFinishReason> = finished(&app).iter().map(|f| f.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![MoveFinishReason::Replaced, MoveFinishReason::Halted]
+        );
+        assert!(app.world().get::<MoveEntityTo>(entity).is_none());
+    }
+
+    #[test]
+    fn test_move_finished_triggers_on_entity() {
+        #[derive(Resource, Default)]
+        struct Arrived(Vec<Entity>);
+
+        let mut app = test_app();
+        app.init_resource::<Arrived>().add_observer(
+            |trigger: Trigger<MoveFinished>, mut arrived: ResMut<Arrived>| {
+                if trigger.reason == MoveFinishReason::Arrived {
+                    arrived.0.push(trigger.target());
+                }
+            },
+        );
+        let entity = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GlobalTransform::default(),
+                move_to(vec2(10.0, 0.0)),
+            ))
+            .id();
+        app.update();
+
+        assert_eq!(app.world().resource::<Arrived>().0, vec![entity]);
+    }
+
+    #[test]
+    fn test_sample_follows_curve() {
+        let move_to = MoveEntityTo {
+            to: vec2(100.0, -100.0),
+            from: Vec2::ZERO,
+            easing: EaseFunction::QuadraticIn,
+            mode: MoveMode::Speed,
+        };
+        assert_eq!(move_to.sample(0.0), Vec2::ZERO);
+        assert_eq!(move_to.sample(0.5), vec2(25.0, -25.0));
+        assert_eq!(move_to.sample(1.0), vec2(100.0, -100.0));
+        //Clamped past the ends
+        assert_eq!(move_to.sample(2.0), vec2(100.0, -100.0));
+    }
+
+    #[test]
+    fn test_timed_move_position_over_time() {
+        let mut app = test_app();
+        let entity = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(0.0, 0.0, 3.0),
+                GlobalTransform::default(),
+                MoveEntityTo {
+                    to: vec2(100.0, 0.0),
+                    from: Vec2::ZERO,
+                    easing: EaseFunction::QuadraticIn,
+                    mode: MoveMode::timed(Duration::from_secs(1)),
+                },
+            ))
+            .id();
+
+        for expected in [6.25, 25.0, 56.25, 100.0] {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(250));
+            app.update();
+            let translation = app.world().get::<Transform>(entity).unwrap().translation;
+            assert!((translation.x - expected).abs() < 1e-3, "{translation}");
+            assert_eq!(translation.z, 3.0);
+        }
+        assert!(app.world().get::<MoveEntityTo>(entity).is_none());
+        assert_eq!(finished(&app).len(), 1);
+    }
+
+    #[test]
+    fn test_only_moves_while_paused_when_marked() {
+        let mut app = App::new();
+        app.add_plugins((bevy::state::app::StatesPlugin, MoveEntityToPlugin))
+            .init_resource::<Time>()
+            .insert_state(AppState::Game)
+            .add_sub_state::<PlayState>()
+            .insert_resource(NextState::Pending(PlayState::Paused));
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        let unit = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GlobalTransform::default(),
+                move_to(vec2(10.0, 0.0)),
+            ))
+            .id();
+        let view = app
+            .world_mut()
+            .spawn((
+                MovesWhilePaused,
+                Transform::default(),
+                GlobalTransform::default(),
+                move_to(vec2(10.0, 0.0)),
+            ))
+            .id();
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world.resource::<State<PlayState>>().get(),
+            &PlayState::Paused
+        );
+        assert!(world.get::<MoveEntityTo>(unit).is_some());
+        assert_eq!(
+            world.get::<Transform>(unit).unwrap().translation,
+            Vec3::ZERO
+        );
+        assert!(world.get::<MoveEntityTo>(view).is_none());
+        assert_eq!(
+            world.get::<Transform>(view).unwrap().translation,
+            vec3(10.0, 0.0, 0.0)
+        );
+    }
+}
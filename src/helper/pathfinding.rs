@@ -0,0 +1,308 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use bevy::{
+    ecs::{removal_detection::RemovedComponents, system::SystemParam},
+    prelude::*,
+};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::{
+    app::AppUpdate,
+    chunk::Chunk,
+    terrain::{TileBroken, TileGrid, TileType, global_tile_at, global_tile_center},
+};
+
+pub struct PathfindingPlugin;
+impl Plugin for PathfindingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PathCache>()
+            .add_systems(Update, invalidate_path_cache.in_set(AppUpdate::PreData));
+    }
+}
+
+///Paths already found keyed by start tile, goal tile and layer
+///Cleared whenever a wall brakes or a chunk gets its tiles or is unloaded
+#[derive(Resource, Default)]
+pub struct PathCache(HashMap<(IVec2, IVec2, i32), Option<Vec<IVec2>>>);
+
+fn invalidate_path_cache(
+    mut broken: EventReader<TileBroken>,
+    loaded: Query<(), (With<Chunk>, Added<TileStorage>)>,
+    mut unloaded: RemovedComponents<Chunk>,
+    mut cache: ResMut<PathCache>,
+) {
+    let broken = broken.read().count() > 0;
+    let unloaded = unloaded.read().count() > 0;
+    if (broken || unloaded || !loaded.is_empty()) && !cache.0.is_empty() {
+        cache.0.clear();
+    }
+}
+
+///Finds paths over the loaded tiles where Ground is open and everything else is solid
+#[derive(SystemParam)]
+pub struct Pathfinder<'w, 's> {
+    tiles: TileGrid<'w, 's>,
+    cache: ResMut<'w, PathCache>,
+}
+
+impl Pathfinder<'_, '_> {
+    ///Most tiles looked at before giving up on a path
+    pub const MAX_SEARCH: usize = 4096;
+
+    pub fn is_open(&self, tile: IVec2, layer: i32) -> bool {
+        self.tiles.tile_type_at(tile, layer) == Some(TileType::Ground)
+    }
+
+    ///Returns the tiles from start to goal
+    ///start and goal are always treated as open so a path can start in or lead into a wall
+    pub fn find_tile_path(&mut self, start: IVec2, goal: IVec2, layer: i32) -> Option<Vec<IVec2>> {
+        let key = (start, goal, layer);
+        if let Some(path) = self.cache.0.get(&key) {
+            return path.clone();
+        }
+        let path = find_path(start, goal, Self::MAX_SEARCH, |tile| {
+            tile == start || tile == goal || self.is_open(tile, layer)
+        });
+        self.cache.0.insert(key, path.clone());
+        path
+    }
+
+    ///Returns the global translations to move through to get next to to
+    ///Only the corners of the path are kept and it ends on the center of the last open tile
+    ///So it stops next to a wall at to instead of going into it
+    pub fn find_path_next_to(&mut self, from: Vec2, to: Vec2, layer: i32) -> Option<Vec<Vec2>> {
+        let mut tiles = self.find_tile_path(global_tile_at(from), global_tile_at(to), layer)?;
+        tiles.pop();
+        let mut waypoints: Vec<Vec2> = simplify_path(&tiles)
+            .into_iter()
+            .skip(1)
+            .map(global_tile_center)
+            .collect();
+        //Already next to it so just get to the middle of the tile it is on
+        if waypoints.is_empty()
+            && let Some(&start) = tiles.first()
+        {
+            waypoints.push(global_tile_center(start));
+        }
+        Some(waypoints)
+    }
+}
+
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+fn heuristic(a: IVec2, b: IVec2) -> u32 {
+    let d = (a - b).abs();
+    let (min, max) = (d.min_element() as u32, d.max_element() as u32);
+    DIAGONAL_COST * min + STRAIGHT_COST * (max - min)
+}
+
+///A* over a grid with diagonal moves that can't cut corners
+///Gives up after looking at max_search tiles
+pub fn find_path(
+    start: IVec2,
+    goal: IVec2,
+    max_search: usize,
+    is_open: impl Fn(IVec2) -> bool,
+) -> Option<Vec<IVec2>> {
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<IVec2, IVec2> = HashMap::new();
+    let mut cost: HashMap<IVec2, u32> = HashMap::new();
+    open.push(Reverse((heuristic(start, goal), start.x, start.y)));
+    cost.insert(start, 0);
+
+    let mut searched = 0;
+    while let Some(Reverse((_, x, y))) = open.pop() {
+        let current = ivec2(x, y);
+        if current == goal {
+            let mut path = vec![goal];
+            let mut tile = goal;
+            while let Some(&previous) = came_from.get(&tile) {
+                path.push(previous);
+                tile = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        searched += 1;
+        if searched > max_search {
+            return None;
+        }
+
+        let current_cost = cost[&current];
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let next = current + ivec2(dx, dy);
+                if !is_open(next) {
+                    continue;
+                }
+                let diagonal = dx != 0 && dy != 0;
+                if diagonal && !(is_open(current + ivec2(dx, 0)) && is_open(current + ivec2(0, dy)))
+                {
+                    continue;
+                }
+                let next_cost = current_cost
+                    + if diagonal {
+                        DIAGONAL_COST
+                    } else {
+                        STRAIGHT_COST
+                    };
+                if cost.get(&next).is_some_and(|&known| known <= next_cost) {
+                    continue;
+                }
+                cost.insert(next, next_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((next_cost + heuristic(next, goal), next.x, next.y)));
+            }
+        }
+    }
+    None
+}
+
+///Removes the tiles in the middle of straight runs
+pub fn simplify_path(tiles: &[IVec2]) -> Vec<IVec2> {
+    let mut simple: Vec<IVec2> = Vec::with_capacity(tiles.len());
+    for (index, &tile) in tiles.iter().enumerate() {
+        let keep = match (index.checked_sub(1), tiles.get(index + 1)) {
+            (Some(previous), Some(&next)) => tile - tiles[previous] != next - tile,
+            _ => true,
+        };
+        if keep {
+            simple.push(tile);
+        }
+    }
+    simple
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> impl Fn(IVec2) -> bool {
+        //Rows are written top to bottom, y goes up
+        let rows: Vec<Vec<bool>> = rows
+            .iter()
+            .rev()
+            .map(|row| row.chars().map(|c| c != '#').collect())
+            .collect();
+        move |tile: IVec2| {
+            if tile.x < 0 || tile.y < 0 {
+                return false;
+            }
+            rows.get(tile.y as usize)
+                .and_then(|row| row.get(tile.x as usize))
+                .copied()
+                .unwrap_or(false)
+        }
+    }
+
+    #[test]
+    fn test_straight_path() {
+        let path = find_path(ivec2(0, 0), ivec2(3, 0), 100, |_| true).unwrap();
+        assert_eq!(
+            path,
+            vec![ivec2(0, 0), ivec2(1, 0), ivec2(2, 0), ivec2(3, 0)]
+        );
+        assert_eq!(simplify_path(&path), vec![ivec2(0, 0), ivec2(3, 0)]);
+    }
+
+    #[test]
+    fn test_path_around_wall() {
+        let open = grid(&[
+            "....", //
+            ".##.", //
+            ".#..", //
+            "....", //
+        ]);
+        let path = find_path(ivec2(0, 1), ivec2(2, 1), 100, open).unwrap();
+        let open = grid(&["....", ".##.", ".#..", "...."]);
+        assert_eq!(path.first(), Some(&ivec2(0, 1)));
+        assert_eq!(path.last(), Some(&ivec2(2, 1)));
+        for pair in path.windows(2) {
+            assert!(open(pair[1]));
+            //Never a jump of more than one tile
+            assert!((pair[1] - pair[0]).abs().max_element() == 1);
+        }
+        assert!(!path.contains(&ivec2(1, 1)));
+    }
+
+    #[test]
+    fn test_no_corner_cutting() {
+        let open = grid(&[
+            ".#", //
+            "#.", //
+        ]);
+        assert!(find_path(ivec2(0, 0), ivec2(1, 1), 100, &open).is_none());
+        assert!(find_path(ivec2(1, 0), ivec2(0, 1), 100, &open).is_none());
+    }
+
+    #[test]
+    fn test_unreachable_gives_up() {
+        //Open world with the goal boxed in
+        let open = |tile: IVec2| (tile - ivec2(5, 5)).abs().max_element() != 1;
+        assert!(find_path(ivec2(0, 0), ivec2(5, 5), 500, open).is_none());
+    }
+
+    #[test]
+    fn test_negative_tiles() {
+        let path = find_path(ivec2(2, 2), ivec2(-12, -3), 1000, |_| true).unwrap();
+        assert_eq!(path.first(), Some(&ivec2(2, 2)));
+        assert_eq!(path.last(), Some(&ivec2(-12, -3)));
+        //5 diagonal steps then 9 straight ones
+        assert_eq!(path.len(), 15);
+        assert_eq!(simplify_path(&path).len(), 3);
+    }
+
+    #[test]
+    fn test_path_next_to_ends_on_ground() {
+        use crate::chunk::ChunkManager;
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut app = App::new();
+        app.init_resource::<ChunkManager>()
+            .init_resource::<PathCache>();
+        //One chunk of Ground with a wall in the middle
+        let wall = uvec2(5, 5);
+        let mut storage = TileStorage::empty(TilemapSize { x: 10, y: 10 });
+        for x in 0..10 {
+            for y in 0..10 {
+                let tile_type = if uvec2(x, y) == wall {
+                    TileType::Wall
+                } else {
+                    TileType::Ground
+                };
+                let tile_pos = TilePos { x, y };
+                storage.set(&tile_pos, app.world_mut().spawn(tile_type).id());
+            }
+        }
+        let chunk = app.world_mut().spawn(storage).id();
+        app.world_mut()
+            .resource_mut::<ChunkManager>()
+            .insert(IVec3::ZERO, chunk);
+
+        let goal = global_tile_center(wall.as_ivec2());
+        let find = move |mut pathfinder: Pathfinder| {
+            let waypoints = pathfinder
+                .find_path_next_to(global_tile_center(ivec2(1, 2)), goal, 0)
+                .unwrap();
+            let last = global_tile_at(*waypoints.last().unwrap());
+            assert_eq!(
+                pathfinder.tiles.tile_type_at(last, 0),
+                Some(TileType::Ground)
+            );
+            assert_eq!((last - wall.as_ivec2()).abs().max_element(), 1);
+
+            //Starting next to it stays on the tile it is on
+            let start = global_tile_center(ivec2(4, 5));
+            let waypoints = pathfinder.find_path_next_to(start, goal, 0).unwrap();
+            assert_eq!(waypoints, vec![start]);
+        };
+        app.world_mut().run_system_once(find).unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+use bevy::prelude::*;
+
+use crate::{
+    app::AppUpdate,
+    chunk::ChunkLayer,
+    game::PlayState,
+    helper::move_entity_to::{FollowPath, MoveEntityTo},
+    terrain::{TILE_SIZE, TileGrid, TileType, global_tile_at, global_tile_center},
+};
+
+pub struct SteeringPlugin;
+impl Plugin for SteeringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            steer
+                .run_if(in_state(PlayState::Playing))
+                .in_set(AppUpdate::PostAction),
+        );
+    }
+}
+
+/// Keeps units apart and off walls while they move
+/// Also slows `MoveEntityTo` and `FollowPath` down when close to the end
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Steering {
+    /// Fastest the steering alone moves a unit in px/s, separate from its travel `Speed`
+    pub max_speed: f32,
+    /// Distance units try to keep between each other
+    pub separation_radius: f32,
+    pub separation: f32,
+    /// Distance from the end of a move where it starts slowing down
+    pub arrival_radius: f32,
+    /// Distance units try to keep from walls
+    pub wall_distance: f32,
+    pub wall_avoidance: f32,
+}
+
+impl Default for Steering {
+    fn default() -> Self {
+        Self {
+            max_speed: TILE_SIZE.x * 2.0,
+            separation_radius: TILE_SIZE.x,
+            separation: 1.0,
+            arrival_radius: TILE_SIZE.x * 2.0,
+            wall_distance: TILE_SIZE.x / 2.0,
+            wall_avoidance: 1.0,
+        }
+    }
+}
+
+impl Steering {
+    /// Slowest a unit moves while arriving so it still gets there
+    pub const MIN_ARRIVAL_FACTOR: f32 = 0.2;
+
+    /// How much of its speed a unit uses with `remaining` distance left to go
+    pub fn arrival_factor(&self, remaining: f32) -> f32 {
+        if self.arrival_radius <= f32::EPSILON {
+            return 1.0;
+        }
+        (remaining / self.arrival_radius).clamp(Self::MIN_ARRIVAL_FACTOR, 1.0)
+    }
+}
+
+/// Push away from every neighbour inside radius, stronger the closer they are
+/// Neighbours on the same spot push along tie_break
+pub fn separation_force(
+    pos: Vec2,
+    neighbours: impl Iterator<Item = Vec2>,
+    radius: f32,
+    tie_break: Vec2,
+) -> Vec2 {
+    let mut force = Vec2::ZERO;
+    for neighbour in neighbours {
+        let offset = pos - neighbour;
+        let distance = offset.length();
+        if distance >= radius {
+            continue;
+        }
+        let direction = if distance > f32::EPSILON {
+            offset / distance
+        } else {
+            tie_break
+        };
+        force += direction * (1.0 - distance / radius);
+    }
+    force
+}
+
+/// Push away from the edges of wall tiles closer than distance
+pub fn wall_force(pos: Vec2, walls: impl Iterator<Item = IVec2>, distance: f32) -> Vec2 {
+    let mut force = Vec2::ZERO;
+    for wall in walls {
+        let center = global_tile_center(wall);
+        let closest = pos.clamp(center - TILE_SIZE / 2.0, center + TILE_SIZE / 2.0);
+        let offset = pos - closest;
+        let wall_distance = offset.length();
+        if wall_distance >= distance {
+            continue;
+        }
+        //Inside the wall so get out the way we are closest to
+        let direction = if wall_distance > f32::EPSILON {
+            offset / wall_distance
+        } else {
+            (pos - center).normalize_or_zero()
+        };
+        force += direction * (1.0 - wall_distance / distance);
+    }
+    force
+}
+
+fn steer(
+    time: Res<Time>,
+    chunk_layer: Res<ChunkLayer>,
+    tiles: TileGrid,
+    mut units: Query<(
+        Entity,
+        &mut Transform,
+        &Steering,
+        Has<MoveEntityTo>,
+        Has<FollowPath>,
+    )>,
+) {
+    let positions: Vec<(Entity, Vec2)> = units
+        .iter()
+        .map(|(entity, transform, ..)| (entity, transform.translation.xy()))
+        .collect();
+    for (entity, mut transform, steering, moving, following) in units.iter_mut() {
+        let pos = transform.translation.xy();
+        let neighbours = positions
+            .iter()
+            .filter(|(other, _)| *other != entity)
+            .map(|&(_, neighbour)| neighbour);
+        //Idle units only get pushed around by others
+        let crowded = neighbours
+            .clone()
+            .any(|neighbour| neighbour.distance(pos) < steering.separation_radius);
+        if !moving && !following && !crowded {
+            continue;
+        }
+        //Spread units on the same spot in different directions
+        let tie_break = Vec2::from_angle(entity.index() as f32 * 2.4);
+        let tile = global_tile_at(pos);
+        let walls = (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |y| tile + ivec2(x, y)))
+            .filter(|&wall| tiles.tile_type_at(wall, **chunk_layer) == Some(TileType::Wall));
+
+        let force = separation_force(pos, neighbours, steering.separation_radius, tie_break)
+            * steering.separation
+            + wall_force(pos, walls, steering.wall_distance) * steering.wall_avoidance;
+        let step = force.clamp_length_max(1.0) * steering.max_speed * time.delta_secs();
+        transform.translation += step.extend(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_separation_force() {
+        let neighbours = [vec2(10.0, 0.0), vec2(0.0, 100.0)];
+        let force = separation_force(Vec2::ZERO, neighbours.into_iter(), 20.0, Vec2::Y);
+        //Only the close one pushes
+        assert_eq!(force, vec2(-0.5, 0.0));
+
+        let force = separation_force(Vec2::ZERO, [Vec2::ZERO].into_iter(), 20.0, Vec2::Y);
+        assert_eq!(force, Vec2::Y);
+    }
+
+    #[test]
+    fn test_wall_force() {
+        //Tile (1, 0) spans 50..100 on x
+        let force = wall_force(vec2(40.0, 25.0), [ivec2(1, 0)].into_iter(), 20.0);
+        assert_eq!(force, vec2(-0.5, 0.0));
+
+        let far = wall_force(vec2(10.0, 25.0), [ivec2(1, 0)].into_iter(), 20.0);
+        assert_eq!(far, Vec2::ZERO);
+
+        //Inside the wall pushes out toward the nearest side
+        let inside = wall_force(vec2(60.0, 25.0), [ivec2(1, 0)].into_iter(), 20.0);
+        assert_eq!(inside, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn test_steer_idle_units() {
+        use crate::chunk::ChunkManager;
+        use bevy::time::TimeUpdateStrategy;
+        use std::time::Duration;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                100,
+            )))
+            .init_resource::<ChunkManager>()
+            .init_resource::<ChunkLayer>()
+            .add_systems(Update, steer);
+        let alone = app
+            .world_mut()
+            .spawn((Steering::default(), Transform::from_xyz(1000.0, 0.0, 0.0)))
+            .id();
+        let crowd = [vec2(0.0, 0.0), vec2(10.0, 0.0)].map(|pos| {
+            app.world_mut()
+                .spawn((
+                    Steering::default(),
+                    Transform::from_translation(pos.extend(0.0)),
+                ))
+                .id()
+        });
+        //Time doesn't advance on the very first update
+        app.update();
+        app.update();
+
+        let pos = |app: &App, entity| app.world().get::<Transform>(entity).unwrap().translation;
+        assert_eq!(pos(&app, alone), vec3(1000.0, 0.0, 0.0));
+        //Pushed apart but no faster than max_speed
+        let max_step = Steering::default().max_speed * 0.1;
+        let left = pos(&app, crowd[0]);
+        let right = pos(&app, crowd[1]);
+        assert!(left.x < 0.0 && left.x >= -max_step);
+        assert!(right.x > 10.0 && right.x <= 10.0 + max_step);
+    }
+
+    #[test]
+    fn test_arrival_factor() {
+        let steering = Steering {
+            arrival_radius: 100.0,
+            ..default()
+        };
+        assert_eq!(steering.arrival_factor(200.0), 1.0);
+        assert_eq!(steering.arrival_factor(50.0), 0.5);
+        assert_eq!(steering.arrival_factor(0.0), Steering::MIN_ARRIVAL_FACTOR);
+    }
+}
@@ -0,0 +1,773 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{context::InputContext, record::replaying_input};
+
+pub struct ActionPlugin;
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionMap>()
+            .init_resource::<PendingRebind>()
+            .add_observer(start_rebind)
+            .add_systems(Startup, load_action_bindings)
+            .add_systems(
+                PreUpdate,
+                (capture_rebind, update_action_map)
+                    .chain()
+                    .run_if(not(replaying_input))
+                    .after(InputSystem),
+            );
+    }
+}
+
+///Everything the player can do with an input
+///Each plugin registers the actions it uses with their default bindings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    DragView,
+    ZoomIn,
+    ZoomOut,
+    ToggleDigMode,
+    DigMark,
+    ///DigMark but with High priority
+    DigMarkHigh,
+    ///DigMark but with Low priority
+    DigMarkLow,
+    DigUnmark,
+    JumpToCore,
+    FollowWisp,
+    SaveBookmark(u8),
+    RecallBookmark(u8),
+    Pause,
+    Resume,
+    CancelTextEntry,
+}
+
+impl InputAction {
+    ///Where the action can fire, the same binding can be used by actions in different contexts
+    pub fn contexts(&self) -> &'static [InputContext] {
+        use InputContext::*;
+        match self {
+            InputAction::MoveUp
+            | InputAction::MoveDown
+            | InputAction::MoveLeft
+            | InputAction::MoveRight
+            | InputAction::DragView
+            | InputAction::ZoomIn
+            | InputAction::ZoomOut
+            | InputAction::JumpToCore
+            | InputAction::FollowWisp
+            | InputAction::SaveBookmark(_)
+            | InputAction::RecallBookmark(_) => &[Gameplay, Paused],
+            InputAction::ToggleDigMode
+            | InputAction::DigMark
+            | InputAction::DigMarkHigh
+            | InputAction::DigMarkLow
+            | InputAction::DigUnmark
+            | InputAction::Pause => &[Gameplay],
+            InputAction::Resume => &[Paused],
+            InputAction::CancelTextEntry => &[TextEntry],
+        }
+    }
+
+    ///If the action can fire in context, None allows everything
+    pub fn allowed_in(&self, context: Option<InputContext>) -> bool {
+        context.is_none_or(|context| self.contexts().contains(&context))
+    }
+
+    ///If the two actions can fire in the same context
+    pub fn shares_context(&self, other: InputAction) -> bool {
+        self.contexts()
+            .iter()
+            .any(|context| other.contexts().contains(context))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputSource {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    ///A button on any connected gamepad
+    Gamepad(GamepadButton),
+}
+
+///Modifier keys that have to be held, either side counts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Self = Self {
+        ctrl: false,
+        shift: false,
+        alt: false,
+    };
+    pub const CTRL: Self = Self {
+        ctrl: true,
+        ..Self::NONE
+    };
+    pub const SHIFT: Self = Self {
+        shift: true,
+        ..Self::NONE
+    };
+
+    pub fn held(keys: &ButtonInput<KeyCode>) -> Self {
+        Self {
+            ctrl: keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+            shift: keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+            alt: keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]),
+        }
+    }
+
+    ///If every modifier in other is also in self
+    pub fn contains(&self, other: Modifiers) -> bool {
+        (self.ctrl || !other.ctrl) && (self.shift || !other.shift) && (self.alt || !other.alt)
+    }
+
+    pub fn count(&self) -> usize {
+        [self.ctrl, self.shift, self.alt]
+            .into_iter()
+            .filter(|&held| held)
+            .count()
+    }
+
+    pub fn is_modifier_key(key: KeyCode) -> bool {
+        matches!(
+            key,
+            KeyCode::ControlLeft
+                | KeyCode::ControlRight
+                | KeyCode::ShiftLeft
+                | KeyCode::ShiftRight
+                | KeyCode::AltLeft
+                | KeyCode::AltRight
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct InputBinding {
+    pub source: InputSource,
+    #[serde(default)]
+    pub modifiers: Modifiers,
+}
+
+impl InputBinding {
+    pub fn key(key: KeyCode) -> Self {
+        Self {
+            source: InputSource::Key(key),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    pub fn mouse(button: MouseButton) -> Self {
+        Self {
+            source: InputSource::Mouse(button),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    pub fn gamepad(button: GamepadButton) -> Self {
+        Self {
+            source: InputSource::Gamepad(button),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    pub fn with(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+}
+
+///The same binding on more than one action in the same context
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActionConflict {
+    pub binding: InputBinding,
+    pub first: InputAction,
+    pub second: InputAction,
+}
+
+///Maps inputs to InputActions and tracks which actions are pressed
+#[derive(Resource, Default)]
+pub struct ActionMap {
+    bindings: HashMap<InputAction, Vec<InputBinding>>,
+    pressed: HashSet<InputAction>,
+    just_pressed: HashSet<InputAction>,
+    just_released: HashSet<InputAction>,
+    ///Inputs that count as not held until they are released
+    suppressed: HashSet<InputSource>,
+    ///Context of the last update
+    context: Option<InputContext>,
+}
+
+///What gets written to the bindings file
+#[derive(Serialize, Deserialize)]
+struct BindingsFile {
+    version: u32,
+    bindings: BTreeMap<InputAction, Vec<InputBinding>>,
+}
+
+impl ActionMap {
+    ///Version of the bindings file, files with any other version are ignored
+    pub const BINDINGS_VERSION: u32 = 1;
+
+    ///Adds default bindings to the action
+    ///Can be called from more than one plugin for the same action
+    pub fn register(
+        &mut self,
+        action: InputAction,
+        defaults: impl IntoIterator<Item = InputBinding>,
+    ) -> &mut Self {
+        let bindings = self.bindings.entry(action).or_default();
+        for binding in defaults {
+            if !bindings.contains(&binding) {
+                bindings.push(binding);
+            }
+        }
+        self
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = InputAction> + '_ {
+        self.bindings.keys().copied()
+    }
+
+    pub fn bindings(&self, action: InputAction) -> &[InputBinding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    ///Binds a slot of action and removes the same binding from other actions in its contexts
+    ///A slot past the end adds a binding and None removes the slot
+    ///Returns the actions the binding was taken from
+    pub fn bind(
+        &mut self,
+        action: InputAction,
+        slot: usize,
+        binding: Option<InputBinding>,
+    ) -> Vec<InputAction> {
+        let mut taken_from = Vec::new();
+        if let Some(binding) = binding {
+            for (&other, bindings) in self.bindings.iter_mut() {
+                if other != action && other.shares_context(action) && bindings.contains(&binding) {
+                    bindings.retain(|&bound| bound != binding);
+                    taken_from.push(other);
+                }
+            }
+        }
+        let bindings = self.bindings.entry(action).or_default();
+        match (binding, slot < bindings.len()) {
+            (Some(binding), true) => bindings[slot] = binding,
+            (Some(binding), false) => bindings.push(binding),
+            (None, true) => {
+                bindings.remove(slot);
+            }
+            (None, false) => {}
+        }
+        taken_from.sort();
+        taken_from
+    }
+
+    ///Every binding on more than one action in the same context
+    pub fn conflicts(&self) -> Vec<ActionConflict> {
+        let mut actions: Vec<InputAction> = self.actions().collect();
+        actions.sort();
+        let mut conflicts = Vec::new();
+        for (index, &first) in actions.iter().enumerate() {
+            for &second in actions[index + 1..]
+                .iter()
+                .filter(|second| first.shares_context(**second))
+            {
+                for &binding in self.bindings(first) {
+                    if self.bindings(second).contains(&binding) {
+                        conflicts.push(ActionConflict {
+                            binding,
+                            first,
+                            second,
+                        });
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: InputAction) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    ///Ignores the input until it is released so holding it doesn't press anything
+    pub fn suppress_until_released(&mut self, source: InputSource) {
+        self.suppressed.insert(source);
+    }
+
+    ///If the binding is held and no binding in context on the same input with more modifiers is
+    ///So Ctrl+1 doesn't also count as 1
+    fn is_active(
+        &self,
+        binding: InputBinding,
+        held: Modifiers,
+        sources: &HashSet<InputSource>,
+        context: Option<InputContext>,
+    ) -> bool {
+        if !sources.contains(&binding.source) || !held.contains(binding.modifiers) {
+            return false;
+        }
+        let shadowed = self
+            .bindings
+            .iter()
+            .filter(|(action, _)| action.allowed_in(context))
+            .flat_map(|(_, bindings)| bindings)
+            .any(|other| {
+                other.source == binding.source
+                    && held.contains(other.modifiers)
+                    && other.modifiers.count() > binding.modifiers.count()
+            });
+        !shadowed
+    }
+
+    ///Sets which actions are pressed from the inputs held this frame
+    ///Actions outside of context are never pressed and inputs held when it changes are ignored
+    pub fn update(
+        &mut self,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+        gamepad: &[GamepadButton],
+        context: Option<InputContext>,
+    ) {
+        let held = Modifiers::held(keys);
+        let mut sources: HashSet<InputSource> = keys
+            .get_pressed()
+            .map(|&key| InputSource::Key(key))
+            .chain(
+                mouse
+                    .get_pressed()
+                    .map(|&button| InputSource::Mouse(button)),
+            )
+            .chain(gamepad.iter().map(|&button| InputSource::Gamepad(button)))
+            .collect();
+        //Inputs held through a context change have to be pressed again
+        //So the Escape that pauses doesn't also resume on the next frame
+        if context != self.context {
+            self.context = context;
+            self.suppressed.extend(sources.iter().copied());
+        }
+        self.suppressed.retain(|source| sources.contains(source));
+        sources.retain(|source| !self.suppressed.contains(source));
+        let pressed: HashSet<InputAction> = self
+            .bindings
+            .iter()
+            .filter(|(action, bindings)| {
+                action.allowed_in(context)
+                    && bindings
+                        .iter()
+                        .any(|&binding| self.is_active(binding, held, &sources, context))
+            })
+            .map(|(&action, _)| action)
+            .collect();
+        self.set_pressed(pressed);
+    }
+
+    ///Every action pressed this frame
+    pub fn pressed_actions(&self) -> impl Iterator<Item = InputAction> + '_ {
+        self.pressed.iter().copied()
+    }
+
+    ///Sets the pressed actions directly, used to feed in recorded input
+    pub fn set_pressed(&mut self, pressed: HashSet<InputAction>) {
+        self.just_pressed = pressed.difference(&self.pressed).copied().collect();
+        self.just_released = self.pressed.difference(&pressed).copied().collect();
+        self.pressed = pressed;
+    }
+
+    ///Where the bindings file is kept
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("expance").join("bindings.ron"))
+    }
+
+    ///Replaces the bindings of every action in the file
+    ///Actions missing from the file keep what they had
+    ///Nothing changes if the file can't be read or has another version
+    pub fn load_from(&mut self, path: &Path) -> io::Result<()> {
+        let text = fs::read_to_string(path)?;
+        let file: BindingsFile =
+            ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if file.version != Self::BINDINGS_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("version {} isn't {}", file.version, Self::BINDINGS_VERSION),
+            ));
+        }
+        self.bindings.extend(file.bindings);
+        Ok(())
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        let file = BindingsFile {
+            version: Self::BINDINGS_VERSION,
+            //Sorted so the file doesn't reorder on every save
+            bindings: self
+                .bindings
+                .iter()
+                .map(|(&action, bindings)| (action, bindings.clone()))
+                .collect(),
+        };
+        let text = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, text)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no user config directory",
+            ));
+        };
+        self.save_to(&path)
+    }
+}
+
+///Lets plugins register their own actions
+pub trait InputActionAppExt {
+    fn register_input_action(
+        &mut self,
+        action: InputAction,
+        defaults: impl IntoIterator<Item = InputBinding>,
+    ) -> &mut Self;
+}
+
+impl InputActionAppExt for App {
+    fn register_input_action(
+        &mut self,
+        action: InputAction,
+        defaults: impl IntoIterator<Item = InputBinding>,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ActionMap>()
+            .register(action, defaults);
+        self
+    }
+}
+
+fn load_action_bindings(mut actions: ResMut<ActionMap>) {
+    let Some(path) = ActionMap::path() else {
+        return;
+    };
+    match actions.load_from(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => warn!(
+            "loading bindings from {} failed, using the defaults:{e}",
+            path.display()
+        ),
+    }
+    for ActionConflict {
+        binding,
+        first,
+        second,
+    } in actions.conflicts()
+    {
+        warn!("{binding:?} is bound to both {first:?} and {second:?}");
+    }
+}
+
+fn update_action_map(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    context: Option<Res<State<InputContext>>>,
+    mut actions: ResMut<ActionMap>,
+) {
+    let gamepad: Vec<GamepadButton> = gamepads
+        .iter()
+        .flat_map(|gamepad| gamepad.get_pressed().copied())
+        .collect();
+    let context = context.map(|context| *context.get());
+    actions.update(&keys, &mouse, &gamepad, context);
+}
+
+///Binds the next key or mouse button pressed to a slot of an action, Escape cancels
+///Modifiers held at the time become part of the binding
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RebindAction {
+    pub action: InputAction,
+    pub slot: usize,
+}
+
+///The rebind waiting for an input
+#[derive(Resource, Default)]
+pub struct PendingRebind(pub Option<RebindAction>);
+
+fn start_rebind(trigger: Trigger<RebindAction>, mut pending: ResMut<PendingRebind>) {
+    pending.0 = Some(*trigger.event());
+}
+
+fn capture_rebind(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut pending: ResMut<PendingRebind>,
+    mut actions: ResMut<ActionMap>,
+) {
+    let Some(RebindAction { action, slot }) = pending.0 else {
+        return;
+    };
+    let key = keys
+        .get_just_pressed()
+        .find(|&&key| !Modifiers::is_modifier_key(key))
+        .copied();
+    let button = mouse.get_just_pressed().next().copied();
+    let source = match (key, button) {
+        (Some(key), _) => InputSource::Key(key),
+        (None, Some(button)) => InputSource::Mouse(button),
+        (None, None) => return,
+    };
+    pending.0 = None;
+    //Keeps it from pressing what it was just bound to, or anything else, while it is held
+    actions.suppress_until_released(source);
+    if source == InputSource::Key(KeyCode::Escape) {
+        return;
+    }
+    let binding = InputBinding {
+        source,
+        modifiers: Modifiers::held(&keys),
+    };
+    for other in actions.bind(action, slot, Some(binding)) {
+        warn!("{binding:?} was unbound from {other:?} to bind it to {action:?}");
+    }
+    if let Err(e) = actions.save() {
+        warn!("saving bindings failed:{e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_map() -> ActionMap {
+        let mut actions = ActionMap::default();
+        actions
+            .register(InputAction::MoveUp, [InputBinding::key(KeyCode::KeyW)])
+            .register(
+                InputAction::RecallBookmark(0),
+                [InputBinding::key(KeyCode::Digit1)],
+            )
+            .register(
+                InputAction::SaveBookmark(0),
+                [InputBinding::key(KeyCode::Digit1).with(Modifiers::CTRL)],
+            )
+            .register(
+                InputAction::DigMark,
+                [InputBinding::mouse(MouseButton::Left)],
+            )
+            .register(
+                InputAction::DigMark,
+                [InputBinding::gamepad(GamepadButton::South)],
+            );
+        actions
+    }
+
+    #[test]
+    fn test_pressed_just_pressed_released() {
+        let mut actions = test_map();
+        let mut keys = ButtonInput::<KeyCode>::default();
+        let mut mouse = ButtonInput::<MouseButton>::default();
+
+        keys.press(KeyCode::KeyW);
+        mouse.press(MouseButton::Left);
+        actions.update(&keys, &mouse, &[], None);
+        assert!(actions.just_pressed(InputAction::MoveUp));
+        assert!(actions.pressed(InputAction::DigMark));
+
+        //Shift isn't bound to anything with W so it doesn't matter
+        keys.press(KeyCode::ShiftLeft);
+        actions.update(&keys, &mouse, &[], None);
+        assert!(actions.pressed(InputAction::MoveUp));
+        assert!(!actions.just_pressed(InputAction::MoveUp));
+
+        keys.release(KeyCode::KeyW);
+        mouse.release(MouseButton::Left);
+        actions.update(&keys, &mouse, &[], None);
+        assert!(actions.just_released(InputAction::MoveUp));
+        assert!(actions.just_released(InputAction::DigMark));
+        assert!(!actions.pressed(InputAction::MoveUp));
+
+        actions.update(&keys, &mouse, &[GamepadButton::South], None);
+        assert!(actions.just_pressed(InputAction::DigMark));
+        assert!(!actions.pressed(InputAction::MoveUp));
+    }
+
+    #[test]
+    fn test_modifiers_pick_the_most_specific_binding() {
+        let mut actions = test_map();
+        let mut keys = ButtonInput::<KeyCode>::default();
+        let mouse = ButtonInput::<MouseButton>::default();
+
+        keys.press(KeyCode::Digit1);
+        actions.update(&keys, &mouse, &[], None);
+        assert!(actions.pressed(InputAction::RecallBookmark(0)));
+        assert!(!actions.pressed(InputAction::SaveBookmark(0)));
+
+        keys.press(KeyCode::ControlRight);
+        actions.update(&keys, &mouse, &[], None);
+        assert!(!actions.pressed(InputAction::RecallBookmark(0)));
+        assert!(actions.just_pressed(InputAction::SaveBookmark(0)));
+    }
+
+    #[test]
+    fn test_contexts() {
+        let mut actions = test_map();
+        actions
+            .register(InputAction::Pause, [InputBinding::key(KeyCode::Escape)])
+            .register(InputAction::Resume, [InputBinding::key(KeyCode::Escape)]);
+        //Same key in different contexts is fine
+        assert!(actions.conflicts().is_empty());
+
+        let mut keys = ButtonInput::<KeyCode>::default();
+        update_keys(&mut actions, &keys, Some(InputContext::Gameplay));
+        keys.press(KeyCode::Escape);
+        update_keys(&mut actions, &keys, Some(InputContext::Gameplay));
+        assert!(actions.just_pressed(InputAction::Pause));
+        assert!(!actions.pressed(InputAction::Resume));
+
+        //Switching context releases what is no longer allowed
+        //and the Escape still held from pausing doesn't resume
+        update_keys(&mut actions, &keys, Some(InputContext::Paused));
+        assert!(actions.just_released(InputAction::Pause));
+        assert!(!actions.pressed(InputAction::Resume));
+        update_keys(&mut actions, &keys, Some(InputContext::Paused));
+        assert!(!actions.just_pressed(InputAction::Resume));
+
+        keys.release(KeyCode::Escape);
+        update_keys(&mut actions, &keys, Some(InputContext::Paused));
+        keys.press(KeyCode::Escape);
+        update_keys(&mut actions, &keys, Some(InputContext::Paused));
+        assert!(actions.just_pressed(InputAction::Resume));
+
+        keys.press(KeyCode::KeyW);
+        update_keys(&mut actions, &keys, Some(InputContext::TextEntry));
+        assert!(!actions.pressed(InputAction::MoveUp));
+        assert!(!actions.pressed(InputAction::Resume));
+    }
+
+    fn update_keys(
+        actions: &mut ActionMap,
+        keys: &ButtonInput<KeyCode>,
+        context: Option<InputContext>,
+    ) {
+        actions.update(keys, &ButtonInput::default(), &[], context);
+    }
+
+    #[test]
+    fn test_bind_takes_binding_from_other_actions() {
+        let mut actions = test_map();
+        assert!(actions.conflicts().is_empty());
+        let taken_from = actions.bind(
+            InputAction::DigMark,
+            1,
+            Some(InputBinding::key(KeyCode::KeyW)),
+        );
+        assert_eq!(taken_from, vec![InputAction::MoveUp]);
+        assert!(actions.bindings(InputAction::MoveUp).is_empty());
+        assert_eq!(actions.bindings(InputAction::DigMark).len(), 2);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut actions = test_map();
+        actions.register(
+            InputAction::MoveDown,
+            [InputBinding::mouse(MouseButton::Left)],
+        );
+        assert_eq!(
+            actions.conflicts(),
+            vec![ActionConflict {
+                binding: InputBinding::mouse(MouseButton::Left),
+                first: InputAction::MoveDown,
+                second: InputAction::DigMark,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_bindings_file_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("expance-bindings-{}.ron", std::process::id()));
+        let mut actions = test_map();
+        actions.bind(
+            InputAction::MoveUp,
+            0,
+            Some(InputBinding::key(KeyCode::ArrowUp)),
+        );
+        actions.save_to(&path).unwrap();
+
+        let mut loaded = test_map();
+        loaded.load_from(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.bindings(InputAction::MoveUp),
+            &[InputBinding::key(KeyCode::ArrowUp)]
+        );
+        assert_eq!(
+            loaded.bindings(InputAction::SaveBookmark(0)),
+            actions.bindings(InputAction::SaveBookmark(0))
+        );
+    }
+
+    #[test]
+    fn test_bindings_file_other_version_keeps_defaults() {
+        let path =
+            std::env::temp_dir().join(format!("expance-bindings-old-{}.ron", std::process::id()));
+        //The KeyboardBindings file that used to live at the same path
+        fs::write(&path, "(toggle_dig_mode: [Some(KeyT), None])").unwrap();
+        let mut actions = test_map();
+        assert!(actions.load_from(&path).is_err());
+
+        fs::write(&path, "(version: 0, bindings: {MoveUp: []})").unwrap();
+        assert!(actions.load_from(&path).is_err());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            actions.bindings(InputAction::MoveUp),
+            &[InputBinding::key(KeyCode::KeyW)]
+        );
+    }
+
+    #[test]
+    fn test_suppressed_input_waits_for_release() {
+        let mut actions = test_map();
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::KeyW);
+        actions.suppress_until_released(InputSource::Key(KeyCode::KeyW));
+        update_keys(&mut actions, &keys, None);
+        assert!(!actions.pressed(InputAction::MoveUp));
+
+        keys.release(KeyCode::KeyW);
+        update_keys(&mut actions, &keys, None);
+        keys.press(KeyCode::KeyW);
+        update_keys(&mut actions, &keys, None);
+        assert!(actions.just_pressed(InputAction::MoveUp));
+    }
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+use crate::{
+    app::{AppState, AppUpdate},
+    game::PlayState,
+    input::{
+        ActionMap, InputAction, InputActionAppExt, InputBinding, InputPlayback, replaying_input,
+    },
+};
+
+pub struct InputContextPlugin;
+impl Plugin for InputContextPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<TextEntry>()
+            .add_computed_state::<InputContext>()
+            .register_input_action(
+                InputAction::CancelTextEntry,
+                [InputBinding::key(KeyCode::Escape)],
+            )
+            .add_systems(
+                Update,
+                cancel_text_entry
+                    .run_if(in_state(TextEntry::Active))
+                    .in_set(AppUpdate::PreData),
+            );
+    }
+}
+
+///Set to Active while a text field has focus so typing doesn't trigger actions
+#[derive(States, Default, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum TextEntry {
+    #[default]
+    Inactive,
+    Active,
+}
+
+///Which InputActions can fire right now
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum InputContext {
+    Menu,
+    Gameplay,
+    Paused,
+    TextEntry,
+}
+
+impl ComputedStates for InputContext {
+    type SourceStates = (AppState, Option<PlayState>, TextEntry);
+
+    fn compute((app_state, play_state, text_entry): Self::SourceStates) -> Option<Self> {
+        if text_entry == TextEntry::Active {
+            return Some(InputContext::TextEntry);
+        }
+        Some(match (app_state, play_state) {
+            (AppState::Game, Some(PlayState::Playing)) => InputContext::Gameplay,
+            (AppState::Game, Some(PlayState::Paused)) => InputContext::Paused,
+            _ => InputContext::Menu,
+        })
+    }
+}
+
+fn cancel_text_entry(actions: Res<ActionMap>, mut next: ResMut<NextState<TextEntry>>) {
+    if actions.just_pressed(InputAction::CancelTextEntry) {
+        next.set(TextEntry::Inactive);
+    }
+}
+
+///Run condition for input that moves the view around, allowed while playing or paused
+///It isn't an action so a replay sets the recorded view instead and it is off while replaying
+pub fn view_input_allowed(
+    context: Option<Res<State<InputContext>>>,
+    playback: Option<Res<InputPlayback>>,
+) -> bool {
+    !replaying_input(playback)
+        && context.is_some_and(|context| {
+            matches!(context.get(), InputContext::Gameplay | InputContext::Paused)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_input_context() {
+        let compute = InputContext::compute;
+        assert_eq!(
+            compute((AppState::Splash, None, TextEntry::Inactive)),
+            Some(InputContext::Menu)
+        );
+        assert_eq!(
+            compute((
+                AppState::Game,
+                Some(PlayState::Playing),
+                TextEntry::Inactive
+            )),
+            Some(InputContext::Gameplay)
+        );
+        assert_eq!(
+            compute((AppState::Game, Some(PlayState::Paused), TextEntry::Inactive)),
+            Some(InputContext::Paused)
+        );
+        assert_eq!(
+            compute((AppState::Game, Some(PlayState::Playing), TextEntry::Active)),
+            Some(InputContext::TextEntry)
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{
+    app::AppUpdate,
+    camera::MainCamera,
+    input::{InputAction, InputActionAppExt, InputBinding, view_input_allowed},
+    player::view::MoveActivePlayerView,
+};
+use bevy::{input::InputSystem, prelude::*, window::PrimaryWindow};
+
+pub struct GamepadPlugin;
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        let defaults = [
+            (InputAction::DigMark, GamepadButton::South),
+            (InputAction::DigUnmark, GamepadButton::East),
+            (InputAction::FollowWisp, GamepadButton::West),
+            (InputAction::JumpToCore, GamepadButton::North),
+            (InputAction::ToggleDigMode, GamepadButton::Select),
+            (InputAction::ZoomIn, GamepadButton::RightTrigger),
+            (InputAction::ZoomOut, GamepadButton::LeftTrigger),
+            (InputAction::Pause, GamepadButton::Start),
+            (InputAction::Resume, GamepadButton::Start),
+        ];
+        for (action, button) in defaults {
+            app.register_input_action(action, [InputBinding::gamepad(button)]);
+        }
+        app.init_resource::<GamepadControls>()
+            .init_resource::<VirtualCursor>()
+            .add_systems(PreUpdate, move_virtual_cursor.after(InputSystem))
+            .add_systems(
+                Update,
+                pan_with_left_stick
+                    .run_if(view_input_allowed)
+                    .in_set(AppUpdate::PreData),
+            );
+    }
+}
+
+///Speeds are in pixels a second at a camera scale of 1.0
+#[derive(Resource)]
+pub struct GamepadControls {
+    ///Sticks pushed less than this are ignored
+    pub deadzone: f32,
+    pub pan_speed: f32,
+    pub cursor_speed: f32,
+}
+
+impl Default for GamepadControls {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            pan_speed: 600.0,
+            cursor_speed: 800.0,
+        }
+    }
+}
+
+impl GamepadControls {
+    ///The stick with the deadzone taken out and rescaled so it still reaches 1.0
+    pub fn apply_deadzone(&self, stick: Vec2) -> Vec2 {
+        let length = stick.length();
+        if length <= self.deadzone {
+            return Vec2::ZERO;
+        }
+        let scaled = ((length - self.deadzone) / (1.0 - self.deadzone)).min(1.0);
+        stick / length * scaled
+    }
+}
+
+///Cursor moved by the right stick in window coordinates
+///Used for CurrsorPositon while active, moving the mouse makes it inactive
+#[derive(Resource, Default)]
+pub struct VirtualCursor {
+    pub pos: Vec2,
+    pub active: bool,
+}
+
+fn move_virtual_cursor(
+    time: Res<Time>,
+    controls: Res<GamepadControls>,
+    gamepads: Query<&Gamepad>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut mouse_moved: EventReader<CursorMoved>,
+    mut cursor: ResMut<VirtualCursor>,
+) {
+    if mouse_moved.read().count() > 0 {
+        cursor.active = false;
+    }
+    let stick: Vec2 = gamepads
+        .iter()
+        .map(|gamepad| controls.apply_deadzone(gamepad.right_stick()))
+        .sum();
+    if stick == Vec2::ZERO {
+        return;
+    }
+    let window = window.single().ok();
+    if !cursor.active {
+        cursor.active = true;
+        if let Some(window) = window {
+            cursor.pos = window.cursor_position().unwrap_or(window.size() / 2.0);
+        }
+    }
+    //Window y points down
+    let step = vec2(stick.x, -stick.y) * controls.cursor_speed * time.delta_secs();
+    cursor.pos += step;
+    if let Some(window) = window {
+        cursor.pos = cursor.pos.clamp(Vec2::ZERO, window.size());
+    }
+}
+
+fn pan_with_left_stick(
+    time: Res<Time>,
+    controls: Res<GamepadControls>,
+    gamepads: Query<&Gamepad>,
+    camera: Query<&Projection, With<MainCamera>>,
+    mut commands: Commands,
+) {
+    let stick: Vec2 = gamepads
+        .iter()
+        .map(|gamepad| controls.apply_deadzone(gamepad.left_stick()))
+        .sum();
+    if stick == Vec2::ZERO {
+        return;
+    }
+    let scale = camera.single().map_or(1.0, MainCamera::scale);
+    let speed = controls.pan_speed * scale * time.delta_secs();
+    commands.trigger(MoveActivePlayerView::By(
+        stick.clamp_length_max(1.0) * speed,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{ActionMap, action::ActionPlugin};
+    use bevy::input::gamepad::{
+        GamepadConnection, GamepadConnectionEvent, RawGamepadAxisChangedEvent,
+        RawGamepadButtonChangedEvent, RawGamepadEvent,
+    };
+    use std::time::Duration;
+
+    fn test_app() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((bevy::input::InputPlugin, ActionPlugin, GamepadPlugin))
+            .init_resource::<Time>()
+            .add_event::<CursorMoved>();
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(500));
+        let gamepad = app.world_mut().spawn_empty().id();
+        app.world_mut().send_event(GamepadConnectionEvent::new(
+            gamepad,
+            GamepadConnection::Connected {
+                name: "Test".into(),
+                vendor_id: None,
+                product_id: None,
+            },
+        ));
+        app.update();
+        (app, gamepad)
+    }
+
+    #[test]
+    fn test_apply_deadzone() {
+        let controls = GamepadControls {
+            deadzone: 0.2,
+            ..default()
+        };
+        assert_eq!(controls.apply_deadzone(vec2(0.1, 0.1)), Vec2::ZERO);
+        assert!(
+            controls
+                .apply_deadzone(vec2(0.0, 0.6))
+                .abs_diff_eq(vec2(0.0, 0.5), 1e-6)
+        );
+        assert_eq!(controls.apply_deadzone(vec2(-1.0, 0.0)), vec2(-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_right_stick_moves_virtual_cursor() {
+        let (mut app, gamepad) = test_app();
+        app.world_mut().spawn((
+            Window {
+                resolution: (1600.0, 1200.0).into(),
+                ..default()
+            },
+            PrimaryWindow,
+        ));
+        assert!(!app.world().resource::<VirtualCursor>().active);
+
+        for axis in [GamepadAxis::RightStickX, GamepadAxis::RightStickY] {
+            app.world_mut()
+                .send_event(RawGamepadEvent::Axis(RawGamepadAxisChangedEvent::new(
+                    gamepad, axis, 1.0,
+                )));
+        }
+        app.update();
+
+        let cursor = app.world().resource::<VirtualCursor>();
+        assert!(cursor.active);
+        //Starts in the middle without a mouse cursor and stick up moves it up the window which is -y
+        assert!(cursor.pos.x > 800.0 && cursor.pos.y < 600.0);
+        assert!(cursor.pos.x < 1600.0 && cursor.pos.y > 0.0);
+
+        //Held against the corner it stays in the window
+        for _ in 0..20 {
+            app.update();
+        }
+        assert_eq!(
+            app.world().resource::<VirtualCursor>().pos,
+            vec2(1600.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_face_buttons_press_actions() {
+        let (mut app, gamepad) = test_app();
+        app.world_mut()
+            .send_event(RawGamepadEvent::Button(RawGamepadButtonChangedEvent::new(
+                gamepad,
+                GamepadButton::South,
+                1.0,
+            )));
+        app.update();
+        let actions = app.world().resource::<ActionMap>();
+        assert!(actions.just_pressed(InputAction::DigMark));
+        assert!(!actions.pressed(InputAction::DigUnmark));
+    }
+}
@@ -1,65 +1,58 @@
-use crate::{app::AppUpdate, player::view::MoveActivePlayerView};
+use crate::{
+    app::AppUpdate,
+    camera::MainCamera,
+    input::{ActionMap, InputAction, InputActionAppExt, InputBinding, PanSettings},
+    player::view::MoveActivePlayerView,
+};
 use bevy::prelude::*;
 
 pub struct KeyboardPlugin;
 impl Plugin for KeyboardPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<KeyboardBindings>()
-            .add_systems(Update, move_current_view.in_set(AppUpdate::PreData));
-    }
-}
-
-#[derive(Resource)]
-pub struct KeyboardBindings {
-    pub move_up: [Option<KeyCode>; 2],
-    pub move_down: [Option<KeyCode>; 2],
-    pub move_left: [Option<KeyCode>; 2],
-    pub move_right: [Option<KeyCode>; 2],
-}
-
-impl KeyboardBindings {
-    pub fn is_pressed_up(&self, keys: &Res<ButtonInput<KeyCode>>) -> bool {
-        keys.any_pressed(self.move_up.iter().filter_map(|&v| v))
-    }
-    pub fn is_pressed_down(&self, keys: &Res<ButtonInput<KeyCode>>) -> bool {
-        keys.any_pressed(self.move_down.iter().filter_map(|&v| v))
-    }
-    pub fn is_pressed_left(&self, keys: &Res<ButtonInput<KeyCode>>) -> bool {
-        keys.any_pressed(self.move_left.iter().filter_map(|&v| v))
-    }
-    pub fn is_pressed_right(&self, keys: &Res<ButtonInput<KeyCode>>) -> bool {
-        keys.any_pressed(self.move_right.iter().filter_map(|&v| v))
-    }
-}
-
-impl Default for KeyboardBindings {
-    fn default() -> Self {
-        Self {
-            move_up: [Some(KeyCode::KeyW), Some(KeyCode::KeyK)],
-            move_down: [Some(KeyCode::KeyS), Some(KeyCode::KeyJ)],
-            move_left: [Some(KeyCode::KeyA), Some(KeyCode::KeyH)],
-            move_right: [Some(KeyCode::KeyD), Some(KeyCode::KeyL)],
-        }
+        app.register_input_action(
+            InputAction::MoveUp,
+            [KeyCode::KeyW, KeyCode::KeyK].map(InputBinding::key),
+        )
+        .register_input_action(
+            InputAction::MoveDown,
+            [KeyCode::KeyS, KeyCode::KeyJ].map(InputBinding::key),
+        )
+        .register_input_action(
+            InputAction::MoveLeft,
+            [KeyCode::KeyA, KeyCode::KeyH].map(InputBinding::key),
+        )
+        .register_input_action(
+            InputAction::MoveRight,
+            [KeyCode::KeyD, KeyCode::KeyL].map(InputBinding::key),
+        )
+        .add_systems(Update, move_current_view.in_set(AppUpdate::PreData));
     }
 }
 
 fn move_current_view(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    bindings: Res<KeyboardBindings>,
+    time: Res<Time>,
+    actions: Res<ActionMap>,
+    settings: Res<PanSettings>,
+    camera: Query<&Projection, With<MainCamera>>,
     mut commands: Commands,
 ) {
     let mut amount = Vec2::ZERO;
-    if bindings.is_pressed_up(&keyboard) {
+    if actions.pressed(InputAction::MoveUp) {
         amount.y += 1.0;
     }
-    if bindings.is_pressed_down(&keyboard) {
+    if actions.pressed(InputAction::MoveDown) {
         amount.y -= 1.0;
     }
-    if bindings.is_pressed_left(&keyboard) {
+    if actions.pressed(InputAction::MoveLeft) {
         amount.x -= 1.0;
     }
-    if bindings.is_pressed_right(&keyboard) {
+    if actions.pressed(InputAction::MoveRight) {
         amount.x += 1.0;
     }
-    commands.trigger(MoveActivePlayerView::By(amount * 5.0));
+    if amount == Vec2::ZERO {
+        return;
+    }
+    let scale = camera.single().map_or(1.0, MainCamera::scale);
+    let speed = settings.key_speed * scale * time.delta_secs();
+    commands.trigger(MoveActivePlayerView::By(amount.normalize() * speed));
 }
@@ -1,9 +1,50 @@
 use bevy::prelude::*;
+mod action;
+mod context;
+mod gamepad;
 mod keyboard;
+mod mouse;
+mod record;
+
+pub use action::{ActionMap, InputAction, InputActionAppExt, InputBinding, Modifiers};
+pub use context::view_input_allowed;
+pub use gamepad::VirtualCursor;
+pub use record::{InputPlayback, live_input, replaying_input};
 
 pub struct InputPlugin;
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(keyboard::KeyboardPlugin);
+        app.init_resource::<PanSettings>().add_plugins((
+            action::ActionPlugin,
+            context::InputContextPlugin,
+            gamepad::GamepadPlugin,
+            keyboard::KeyboardPlugin,
+            mouse::MousePlugin,
+            record::InputRecordPlugin,
+        ));
+    }
+}
+
+///How the PlayerView gets panned around
+///Speeds are in pixels a second at a camera scale of 1.0
+#[derive(Resource)]
+pub struct PanSettings {
+    pub key_speed: f32,
+    pub edge_scroll: bool,
+    pub edge_speed: f32,
+    ///How close to the edge of the window in pixels the cursor has to be to scroll
+    pub edge_margin: f32,
+    pub drag: bool,
+}
+
+impl Default for PanSettings {
+    fn default() -> Self {
+        Self {
+            key_speed: 300.0,
+            edge_scroll: true,
+            edge_speed: 300.0,
+            edge_margin: 10.0,
+            drag: true,
+        }
     }
 }
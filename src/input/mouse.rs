@@ -0,0 +1,123 @@
+use crate::{
+    app::AppUpdate,
+    camera::MainCamera,
+    input::{
+        ActionMap, InputAction, InputActionAppExt, InputBinding, PanSettings, view_input_allowed,
+    },
+    player::view::MoveActivePlayerView,
+};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+pub struct MousePlugin;
+impl Plugin for MousePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_input_action(
+            InputAction::DragView,
+            [InputBinding::mouse(MouseButton::Middle)],
+        )
+        .add_systems(
+            Update,
+            (
+                edge_scroll_current_view.run_if(view_input_allowed),
+                drag_current_view.run_if(view_input_allowed),
+            )
+                .in_set(AppUpdate::PreData),
+        );
+    }
+}
+
+///Direction to scroll with the cursor at cursor in a window of size
+///Window y points down so it gets flipped
+pub fn edge_scroll_direction(cursor: Vec2, size: Vec2, margin: f32) -> Vec2 {
+    let mut direction = Vec2::ZERO;
+    if cursor.x <= margin {
+        direction.x -= 1.0;
+    }
+    if cursor.x >= size.x - margin {
+        direction.x += 1.0;
+    }
+    if cursor.y <= margin {
+        direction.y += 1.0;
+    }
+    if cursor.y >= size.y - margin {
+        direction.y -= 1.0;
+    }
+    direction.normalize_or_zero()
+}
+
+fn edge_scroll_current_view(
+    time: Res<Time>,
+    settings: Res<PanSettings>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<&Projection, With<MainCamera>>,
+    mut commands: Commands,
+) {
+    if !settings.edge_scroll {
+        return;
+    }
+    let Ok(window) = window.single() else {
+        return;
+    };
+    if !window.focused {
+        return;
+    }
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let direction = edge_scroll_direction(cursor, window.size(), settings.edge_margin);
+    if direction == Vec2::ZERO {
+        return;
+    }
+    let scale = camera.single().map_or(1.0, MainCamera::scale);
+    let speed = settings.edge_speed * scale * time.delta_secs();
+    commands.trigger(MoveActivePlayerView::By(direction * speed));
+}
+
+///Keeps the point grabbed under the cursor while DragView is held
+fn drag_current_view(
+    actions: Res<ActionMap>,
+    settings: Res<PanSettings>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<&Projection, With<MainCamera>>,
+    mut last: Local<Option<Vec2>>,
+    mut commands: Commands,
+) {
+    let cursor = window.single().ok().and_then(Window::cursor_position);
+    if !settings.drag || !actions.pressed(InputAction::DragView) {
+        *last = None;
+        return;
+    }
+    let Some(cursor) = cursor else {
+        return;
+    };
+    if let Some(last) = last.replace(cursor) {
+        let delta = cursor - last;
+        if delta == Vec2::ZERO {
+            return;
+        }
+        let scale = camera.single().map_or(1.0, MainCamera::scale);
+        commands.trigger(MoveActivePlayerView::By(vec2(-delta.x, delta.y) * scale));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_scroll_direction() {
+        let size = vec2(800.0, 600.0);
+        assert_eq!(
+            edge_scroll_direction(vec2(400.0, 300.0), size, 10.0),
+            Vec2::ZERO
+        );
+        assert_eq!(
+            edge_scroll_direction(vec2(5.0, 300.0), size, 10.0),
+            Vec2::NEG_X
+        );
+        //Top of the window is up in the world
+        assert_eq!(edge_scroll_direction(vec2(400.0, 0.0), size, 10.0), Vec2::Y);
+        let corner = edge_scroll_direction(vec2(800.0, 600.0), size, 10.0);
+        assert!((corner - vec2(1.0, -1.0).normalize()).length() < 1e-6);
+    }
+}
@@ -0,0 +1,427 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    time::{TimeSystem, TimeUpdateStrategy},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppState,
+    camera::MainCamera,
+    cursor::CurrsorPositon,
+    input::{ActionMap, InputAction},
+    player::view::PlayerView,
+};
+
+///Records the ActionMap, cursor and view every frame so a session can be replayed
+///Start with `--record <file>` or `--replay <file>`, both begin when the game starts
+///Input that isn't an action (edge scroll, drag, wheel and stick) is only off while replaying
+///It moves the view and the view is recorded instead
+pub struct InputRecordPlugin;
+impl Plugin for InputRecordPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputPlayback>()
+            .add_observer(start_input_recording)
+            .add_observer(stop_input_recording)
+            .add_observer(replay_input)
+            .add_systems(OnEnter(AppState::Game), start_playback_from_args)
+            .add_systems(
+                First,
+                (
+                    set_replay_delta.before(TimeSystem),
+                    start_recorded_frame.after(TimeSystem),
+                ),
+            )
+            .add_systems(
+                PreUpdate,
+                apply_replay_frame
+                    .run_if(replaying_input)
+                    .after(InputSystem),
+            )
+            .add_systems(Last, (record_frame, save_recording_on_exit).chain());
+    }
+}
+
+///What happened in a single frame
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    pub delta: Duration,
+    pub pressed: Vec<InputAction>,
+    ///Cursor in world space
+    pub cursor: Vec2,
+    ///The view at the start of the frame, None without a MainCamera
+    #[serde(default)]
+    pub view: Option<RecordedView>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RecordedView {
+    pub translation: Vec2,
+    pub scale: f32,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct InputRecording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        let text = ron::ser::to_string(self).map_err(io::Error::other)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, text)
+    }
+}
+
+///Where input is coming from
+#[derive(Resource, Default)]
+pub enum InputPlayback {
+    #[default]
+    Live,
+    ///Live input being recorded, saved to path when stopped or on exit
+    Recording {
+        path: PathBuf,
+        recording: InputRecording,
+    },
+    ///Recorded input fed back in place of live input
+    Replaying {
+        recording: InputRecording,
+        frame: usize,
+    },
+}
+
+impl InputPlayback {
+    ///The frame being replayed
+    fn replay_frame(&self) -> Option<&RecordedFrame> {
+        match self {
+            InputPlayback::Replaying { recording, frame } => recording.frames.get(*frame),
+            _ => None,
+        }
+    }
+}
+
+///Run condition for systems reading live input, false while replaying
+pub fn replaying_input(playback: Option<Res<InputPlayback>>) -> bool {
+    playback.is_some_and(|playback| matches!(*playback, InputPlayback::Replaying { .. }))
+}
+
+///Run condition that is true with neither a recording nor a replay going
+pub fn live_input(playback: Option<Res<InputPlayback>>) -> bool {
+    playback.is_none_or(|playback| matches!(*playback, InputPlayback::Live))
+}
+
+#[derive(Event)]
+pub struct StartInputRecording(pub PathBuf);
+
+#[derive(Event)]
+pub struct StopInputRecording;
+
+#[derive(Event)]
+pub struct ReplayInput(pub PathBuf);
+
+fn start_playback_from_args(mut commands: Commands) {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--record", Some(path)) => commands.trigger(StartInputRecording(path.into())),
+            ("--replay", Some(path)) => commands.trigger(ReplayInput(path.into())),
+            ("--record" | "--replay", None) => warn!("{arg} needs a file"),
+            _ => {}
+        }
+    }
+}
+
+fn start_input_recording(
+    trigger: Trigger<StartInputRecording>,
+    mut playback: ResMut<InputPlayback>,
+) {
+    if !matches!(*playback, InputPlayback::Live) {
+        warn!("can't start recording input while already recording or replaying");
+        return;
+    }
+    *playback = InputPlayback::Recording {
+        path: trigger.event().0.clone(),
+        recording: InputRecording::default(),
+    };
+}
+
+fn stop_input_recording(
+    _trigger: Trigger<StopInputRecording>,
+    mut playback: ResMut<InputPlayback>,
+) {
+    let InputPlayback::Recording { path, recording } = &*playback else {
+        return;
+    };
+    match recording.save_to(path) {
+        Ok(()) => info!(
+            "saved {} frames of input to {}",
+            recording.frames.len(),
+            path.display()
+        ),
+        Err(e) => warn!("saving input recording to {} failed:{e}", path.display()),
+    }
+    *playback = InputPlayback::Live;
+}
+
+fn replay_input(trigger: Trigger<ReplayInput>, mut playback: ResMut<InputPlayback>) {
+    let path = &trigger.event().0;
+    if !matches!(*playback, InputPlayback::Live) {
+        warn!("can't replay input while already recording or replaying");
+        return;
+    }
+    match InputRecording::load_from(path) {
+        Ok(recording) => {
+            *playback = InputPlayback::Replaying {
+                recording,
+                frame: 0,
+            }
+        }
+        Err(e) => warn!("loading input recording from {} failed:{e}", path.display()),
+    }
+}
+
+///Time has to advance like it did when recorded or movement drifts
+fn set_replay_delta(playback: Res<InputPlayback>, mut strategy: ResMut<TimeUpdateStrategy>) {
+    match playback.replay_frame() {
+        Some(frame) => *strategy = TimeUpdateStrategy::ManualDuration(frame.delta),
+        None if matches!(*strategy, TimeUpdateStrategy::ManualDuration(_)) => {
+            *strategy = TimeUpdateStrategy::Automatic;
+        }
+        None => {}
+    }
+}
+
+fn apply_replay_frame(
+    mut playback: ResMut<InputPlayback>,
+    mut actions: ResMut<ActionMap>,
+    mut cursor: ResMut<CurrsorPositon>,
+    mut camera: Query<(&ChildOf, &mut Projection), With<MainCamera>>,
+    mut views: Query<&mut Transform, With<PlayerView>>,
+) {
+    let Some(frame) = playback.replay_frame() else {
+        info!("input replay finished");
+        *playback = InputPlayback::Live;
+        actions.set_pressed(HashSet::new());
+        return;
+    };
+    actions.set_pressed(frame.pressed.iter().copied().collect());
+    **cursor = frame.cursor;
+    if let Some(view) = frame.view
+        && let Ok((parent, mut projection)) = camera.single_mut()
+        && let Ok(mut transform) = views.get_mut(parent.parent())
+    {
+        transform.translation = view.translation.extend(transform.translation.z);
+        if let Projection::Orthographic(ortho) = &mut *projection {
+            ortho.scale = view.scale;
+        }
+    }
+    if let InputPlayback::Replaying { frame, .. } = &mut *playback {
+        *frame += 1;
+    }
+}
+
+///Frames start in First so a recording started partway through a frame begins on the next one
+///The same as a replay does
+fn start_recorded_frame(
+    time: Res<Time>,
+    mut playback: ResMut<InputPlayback>,
+    camera: Query<(&GlobalTransform, &Projection), With<MainCamera>>,
+) {
+    let InputPlayback::Recording { recording, .. } = &mut *playback else {
+        return;
+    };
+    let view = camera
+        .single()
+        .ok()
+        .map(|(transform, projection)| RecordedView {
+            translation: transform.translation().xy(),
+            scale: MainCamera::scale(projection),
+        });
+    recording.frames.push(RecordedFrame {
+        delta: time.delta(),
+        pressed: Vec::new(),
+        cursor: Vec2::ZERO,
+        view,
+    });
+}
+
+fn record_frame(
+    actions: Res<ActionMap>,
+    cursor: Res<CurrsorPositon>,
+    mut playback: ResMut<InputPlayback>,
+) {
+    let InputPlayback::Recording { recording, .. } = &mut *playback else {
+        return;
+    };
+    let Some(frame) = recording.frames.last_mut() else {
+        return;
+    };
+    frame.pressed = actions.pressed_actions().collect();
+    //Sorted so the file is the same for the same input
+    frame.pressed.sort();
+    frame.cursor = **cursor;
+}
+
+fn save_recording_on_exit(exit: EventReader<AppExit>, mut commands: Commands) {
+    if !exit.is_empty() {
+        commands.trigger(StopInputRecording);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{InputActionAppExt, InputBinding, action::ActionPlugin};
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::input::InputPlugin, ActionPlugin))
+            .init_resource::<CurrsorPositon>()
+            .init_resource::<InputPlayback>()
+            .add_systems(
+                First,
+                (
+                    set_replay_delta.before(TimeSystem),
+                    start_recorded_frame.after(TimeSystem),
+                ),
+            )
+            .add_systems(
+                PreUpdate,
+                apply_replay_frame
+                    .run_if(replaying_input)
+                    .after(InputSystem),
+            )
+            .add_systems(Last, record_frame)
+            .register_input_action(InputAction::MoveUp, [InputBinding::key(KeyCode::KeyW)]);
+        app
+    }
+
+    #[test]
+    fn test_replay_feeds_recorded_frames() {
+        let frames = vec![
+            RecordedFrame {
+                delta: Duration::from_millis(20),
+                pressed: vec![InputAction::DigMark],
+                cursor: vec2(10.0, -5.0),
+                view: None,
+            },
+            RecordedFrame {
+                delta: Duration::from_millis(30),
+                pressed: vec![],
+                cursor: vec2(12.0, -5.0),
+                view: None,
+            },
+        ];
+        let mut app = test_app();
+        //Time doesn't advance on the very first update
+        app.update();
+        app.insert_resource(InputPlayback::Replaying {
+            recording: InputRecording {
+                frames: frames.clone(),
+            },
+            frame: 0,
+        });
+
+        app.update();
+        let actions = app.world().resource::<ActionMap>();
+        assert!(actions.just_pressed(InputAction::DigMark));
+        assert_eq!(**app.world().resource::<CurrsorPositon>(), vec2(10.0, -5.0));
+        assert_eq!(app.world().resource::<Time>().delta(), frames[0].delta);
+
+        app.update();
+        let actions = app.world().resource::<ActionMap>();
+        assert!(actions.just_released(InputAction::DigMark));
+        assert_eq!(app.world().resource::<Time>().delta(), frames[1].delta);
+
+        //Back to live once the recording runs out
+        app.update();
+        assert!(matches!(
+            *app.world().resource::<InputPlayback>(),
+            InputPlayback::Live
+        ));
+    }
+
+    #[test]
+    fn test_recording_round_trip() {
+        let mut app = test_app();
+        let path =
+            std::env::temp_dir().join(format!("expance_test_recording_{}.ron", std::process::id()));
+        app.insert_resource(InputPlayback::Recording {
+            path: path.clone(),
+            recording: InputRecording::default(),
+        });
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyW);
+        **app.world_mut().resource_mut::<CurrsorPositon>() = vec2(3.0, 4.0);
+        app.update();
+        app.update();
+
+        let InputPlayback::Recording { recording, .. } = app.world().resource::<InputPlayback>()
+        else {
+            panic!("not recording");
+        };
+        assert_eq!(recording.frames.len(), 2);
+        assert_eq!(recording.frames[0].pressed, vec![InputAction::MoveUp]);
+        assert_eq!(recording.frames[1].cursor, vec2(3.0, 4.0));
+        assert_eq!(recording.frames[1].view, None);
+
+        recording.save_to(&path).unwrap();
+        let loaded = InputRecording::load_from(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(&loaded, recording);
+    }
+
+    #[test]
+    fn test_replay_moves_view() {
+        let mut app = test_app();
+        let camera = app
+            .world_mut()
+            .spawn((
+                MainCamera,
+                Projection::Orthographic(OrthographicProjection::default_2d()),
+            ))
+            .id();
+        let view = app
+            .world_mut()
+            .spawn((PlayerView, Transform::from_xyz(0.0, 0.0, 5.0)))
+            .add_child(camera)
+            .id();
+        app.update();
+        let recorded = RecordedView {
+            translation: vec2(-250.0, 40.0),
+            scale: 2.0,
+        };
+        app.insert_resource(InputPlayback::Replaying {
+            recording: InputRecording {
+                frames: vec![RecordedFrame {
+                    delta: Duration::from_millis(20),
+                    pressed: vec![],
+                    cursor: Vec2::ZERO,
+                    view: Some(recorded),
+                }],
+            },
+            frame: 0,
+        });
+
+        app.update();
+        let transform = app.world().get::<Transform>(view).unwrap();
+        assert_eq!(transform.translation, vec3(-250.0, 40.0, 5.0));
+        let projection = app.world().get::<Projection>(camera).unwrap();
+        assert_eq!(MainCamera::scale(projection), 2.0);
+    }
+}
@@ -31,6 +31,7 @@ fn main() -> AppExit {
         chunk::ChunkPlugin,
         game::GamePlugin,
         input::InputPlugin,
+        domain::DomainPlugin,
     ));
     app.run()
 }
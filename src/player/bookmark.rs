@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+
+use crate::{
+    app::AppUpdate,
+    camera::MainCamera,
+    input::{ActionMap, InputAction, InputActionAppExt, InputBinding, Modifiers},
+    player::{OwnedBy, Player, core::PlayerCore, view::MoveActivePlayerView},
+};
+
+pub struct CameraBookmarkPlugin;
+impl Plugin for CameraBookmarkPlugin {
+    fn build(&self, app: &mut App) {
+        const DIGITS: [KeyCode; CameraBookmarks::COUNT] = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+        for (slot, key) in DIGITS.into_iter().enumerate() {
+            let slot = slot as u8;
+            app.register_input_action(InputAction::RecallBookmark(slot), [InputBinding::key(key)])
+                .register_input_action(
+                    InputAction::SaveBookmark(slot),
+                    [InputBinding::key(key).with(Modifiers::CTRL)],
+                );
+        }
+        app.register_input_action(
+            InputAction::JumpToCore,
+            [KeyCode::Home, KeyCode::Space].map(InputBinding::key),
+        )
+        .add_observer(save_camera_bookmark)
+        .add_observer(recall_camera_bookmark)
+        .add_observer(jump_to_core)
+        .add_systems(Update, use_camera_bookmarks.in_set(AppUpdate::PreData));
+    }
+}
+
+///Saved view positions of a Player
+#[derive(Component, Default)]
+pub struct CameraBookmarks([Option<Vec2>; CameraBookmarks::COUNT]);
+
+impl CameraBookmarks {
+    pub const COUNT: usize = 9;
+
+    pub fn get(&self, slot: usize) -> Option<Vec2> {
+        self.0.get(slot).copied().flatten()
+    }
+
+    pub fn set(&mut self, slot: usize, pos: Vec2) {
+        if let Some(bookmark) = self.0.get_mut(slot) {
+            *bookmark = Some(pos);
+        }
+    }
+}
+
+///Saves where the active view is into a bookmark slot of the Player
+#[derive(Event)]
+pub struct SaveCameraBookmark(pub usize);
+
+///Moves the active view to a bookmark slot of the Player
+#[derive(Event)]
+pub struct RecallCameraBookmark(pub usize);
+
+///Moves the active view to the PlayerCore of the Player
+#[derive(Event)]
+pub struct JumpToCore;
+
+fn save_camera_bookmark(
+    trigger: Trigger<SaveCameraBookmark>,
+    camera: Query<&GlobalTransform, With<MainCamera>>,
+    mut player: Query<&mut CameraBookmarks, With<Player>>,
+) {
+    let (Ok(camera), Ok(mut bookmarks)) = (camera.single(), player.single_mut()) else {
+        return;
+    };
+    bookmarks.set(trigger.0, camera.translation().xy());
+}
+
+fn recall_camera_bookmark(
+    trigger: Trigger<RecallCameraBookmark>,
+    player: Query<&CameraBookmarks, With<Player>>,
+    mut commands: Commands,
+) {
+    let Ok(bookmarks) = player.single() else {
+        return;
+    };
+    if let Some(pos) = bookmarks.get(trigger.0) {
+        commands.trigger(MoveActivePlayerView::To(pos));
+    }
+}
+
+fn jump_to_core(
+    _trigger: Trigger<JumpToCore>,
+    player: Query<Entity, With<Player>>,
+    cores: Query<(&GlobalTransform, &OwnedBy), With<PlayerCore>>,
+    mut commands: Commands,
+) {
+    let Ok(player) = player.single() else {
+        return;
+    };
+    let Some((core, _)) = cores.iter().find(|&(_, &OwnedBy(owner))| owner == player) else {
+        return;
+    };
+    commands.trigger(MoveActivePlayerView::To(core.translation().xy()));
+}
+
+fn use_camera_bookmarks(actions: Res<ActionMap>, mut commands: Commands) {
+    if actions.just_pressed(InputAction::JumpToCore) {
+        commands.trigger(JumpToCore);
+    }
+    for slot in 0..CameraBookmarks::COUNT {
+        if actions.just_pressed(InputAction::SaveBookmark(slot as u8)) {
+            commands.trigger(SaveCameraBookmark(slot));
+        }
+        if actions.just_pressed(InputAction::RecallBookmark(slot as u8)) {
+            commands.trigger(RecallCameraBookmark(slot));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_bookmarks() {
+        let mut bookmarks = CameraBookmarks::default();
+        assert_eq!(bookmarks.get(0), None);
+        bookmarks.set(0, vec2(1.0, 2.0));
+        bookmarks.set(CameraBookmarks::COUNT, Vec2::ONE);
+        assert_eq!(bookmarks.get(0), Some(vec2(1.0, 2.0)));
+        assert_eq!(bookmarks.get(CameraBookmarks::COUNT), None);
+    }
+}
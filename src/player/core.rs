@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy_asset_loader::asset_collection::AssetCollection;
 
 use crate::{
+    chunk::ChunkLoader,
     player::{OwnedBy, Player},
     terrain::{BrakeTile, TILE_SIZE, brake_all_tiles_around},
 };
@@ -16,6 +17,11 @@ impl Plugin for PlayerCorePlugin {
 #[derive(Component, Default)]
 pub struct PlayerCore;
 
+impl PlayerCore {
+    ///Chunks around the core stay loaded wherever the camera goes
+    pub const LOADER: ChunkLoader = ChunkLoader(ivec3(1, 1, 0));
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct PlayerCoreSprite {
     #[asset(path = "placeholder/Diamond/Sprite-0001.png")]
@@ -31,6 +37,12 @@ fn spawn_player_core(
     info!("spawn player core");
     let sprite = Sprite::from_image(sprite_texture.default.clone());
     let transform = Transform::from_xyz(TILE_SIZE.x / 2.0, TILE_SIZE.y / 2.0, 1.1);
-    commands.spawn((PlayerCore, transform, sprite, OwnedBy(trigger.target())));
+    commands.spawn((
+        PlayerCore,
+        transform,
+        sprite,
+        OwnedBy(trigger.target()),
+        PlayerCore::LOADER,
+    ));
     brake_all_tiles_around(transform.translation.xy(), 0, 1, &mut tile_brakes);
 }
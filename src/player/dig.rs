@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use strum::FromRepr;
+
+use crate::{
+    app::AppUpdate,
+    chunk::ChunkLayer,
+    cursor::CurrsorPositon,
+    input::{ActionMap, InputAction, InputActionAppExt, InputBinding, Modifiers},
+    player::Player,
+    terrain::{
+        ChunkModified, TILE_SIZE, TerrainType, TileBroken, TileGrid, TileType, global_tile_at,
+        global_tile_center, split_global_tile,
+    },
+};
+
+pub struct DigPlugin;
+impl Plugin for DigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<DigMode>()
+            .register_input_action(
+                InputAction::ToggleDigMode,
+                [InputBinding::key(KeyCode::KeyG)],
+            )
+            .register_input_action(
+                InputAction::DigMark,
+                [InputBinding::mouse(MouseButton::Left)],
+            )
+            .register_input_action(
+                InputAction::DigMarkHigh,
+                [InputBinding::mouse(MouseButton::Left).with(Modifiers::SHIFT)],
+            )
+            .register_input_action(
+                InputAction::DigMarkLow,
+                [InputBinding::mouse(MouseButton::Left).with(Modifiers::CTRL)],
+            )
+            .register_input_action(
+                InputAction::DigUnmark,
+                [InputBinding::mouse(MouseButton::Right)],
+            )
+            .add_observer(highlight_designated_tile)
+            .add_observer(unhighlight_designated_tile)
+            .add_systems(Update, toggle_dig_mode.in_set(AppUpdate::PreData))
+            .add_systems(
+                Update,
+                designate_dig_area
+                    .run_if(in_state(DigMode::On))
+                    .in_set(AppUpdate::Data),
+            )
+            .add_systems(
+                Update,
+                remove_dug_designations.in_set(AppUpdate::PostAction),
+            );
+    }
+}
+
+///While On dragging with DigMark marks walls for digging and DigUnmark unmarks them
+///DigMarkHigh and DigMarkLow mark them with High and Low priority
+#[derive(States, Default, Clone, Eq, PartialEq, Hash, Debug, Copy)]
+pub enum DigMode {
+    On,
+    #[default]
+    Off,
+}
+
+///A wall tile marked for digging
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DigDesignation {
+    ///The Player entity that marked it
+    pub player: Entity,
+    pub priority: DigPriority,
+}
+
+///Higher priority tiles get dug first
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, FromRepr)]
+#[repr(u8)]
+pub enum DigPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl DigDesignation {
+    const HIGHLIGHT: Color = Color::srgb(1.0, 0.5, 0.0);
+    const HIGHLIGHT_AMOUNT: f32 = 0.5;
+}
+
+fn highlight_designated_tile(
+    trigger: Trigger<OnAdd, DigDesignation>,
+    mut tiles: Query<(&TerrainType, &mut TileColor)>,
+) {
+    if let Ok((terrain_type, mut color)) = tiles.get_mut(trigger.target()) {
+        color.0 = terrain_type
+            .get_color()
+            .mix(&DigDesignation::HIGHLIGHT, DigDesignation::HIGHLIGHT_AMOUNT);
+    }
+}
+
+fn unhighlight_designated_tile(
+    trigger: Trigger<OnRemove, DigDesignation>,
+    mut tiles: Query<(&TerrainType, &mut TileColor)>,
+) {
+    if let Ok((terrain_type, mut color)) = tiles.get_mut(trigger.target()) {
+        color.0 = terrain_type.get_color();
+    }
+}
+
+///Global tiles covered by the rectangle between two global translations
+pub fn tiles_in_rect(a: Vec2, b: Vec2) -> impl Iterator<Item = IVec2> {
+    let a = global_tile_at(a);
+    let b = global_tile_at(b);
+    let min = a.min(b);
+    let max = a.max(b);
+    (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| ivec2(x, y)))
+}
+
+fn toggle_dig_mode(
+    actions: Res<ActionMap>,
+    dig_mode: Res<State<DigMode>>,
+    mut next: ResMut<NextState<DigMode>>,
+) {
+    if !actions.just_pressed(InputAction::ToggleDigMode) {
+        return;
+    }
+    next.set(match dig_mode.get() {
+        DigMode::On => DigMode::Off,
+        DigMode::Off => DigMode::On,
+    });
+}
+
+fn designate_dig_area(
+    actions: Res<ActionMap>,
+    cursor: Res<CurrsorPositon>,
+    chunk_layer: Res<ChunkLayer>,
+    mut drag: Local<Option<(Vec2, InputAction)>>,
+    player: Query<Entity, With<Player>>,
+    tiles: TileGrid,
+    designations: Query<&DigDesignation>,
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+) {
+    for action in [
+        InputAction::DigMark,
+        InputAction::DigMarkHigh,
+        InputAction::DigMarkLow,
+        InputAction::DigUnmark,
+    ] {
+        if actions.just_pressed(action) {
+            *drag = Some((**cursor, action));
+        }
+    }
+    let Some((start, action)) = *drag else {
+        return;
+    };
+
+    if actions.pressed(action) {
+        //Preview the tiles that will be marked
+        let min = global_tile_center(global_tile_at(start.min(**cursor))) - TILE_SIZE / 2.0;
+        let max = global_tile_center(global_tile_at(start.max(**cursor))) + TILE_SIZE / 2.0;
+        let color = match action {
+            InputAction::DigUnmark => Color::WHITE,
+            _ => DigDesignation::HIGHLIGHT,
+        };
+        gizmos.rect_2d((min + max) / 2.0, max - min, color);
+        return;
+    }
+    *drag = None;
+    if !actions.just_released(action) {
+        //The mode was left while dragging
+        return;
+    }
+    let Ok(player) = player.single() else {
+        return;
+    };
+    let priority = match action {
+        InputAction::DigMarkHigh => DigPriority::High,
+        InputAction::DigMarkLow => DigPriority::Low,
+        _ => DigPriority::Normal,
+    };
+
+    for tile in tiles_in_rect(start, **cursor) {
+        let Some(tile_id) = tiles.tile_at(tile, **chunk_layer) else {
+            continue;
+        };
+        let marked = designations.get(tile_id).ok();
+        let changed = match action {
+            InputAction::DigUnmark => marked.is_some(),
+            _ => {
+                tiles.tile_type_at(tile, **chunk_layer) == Some(TileType::Wall)
+                    && marked
+                        .is_none_or(|marked| marked.player != player || marked.priority != priority)
+            }
+        };
+        if !changed {
+            continue;
+        }
+        match action {
+            InputAction::DigUnmark => {
+                commands.entity(tile_id).remove::<DigDesignation>();
+            }
+            _ => {
+                commands
+                    .entity(tile_id)
+                    .try_insert(DigDesignation { player, priority });
+            }
+        }
+        //Marks are saved with the chunk
+        let (chunk_pos, _) = split_global_tile(tile);
+        if let Some(chunk_id) = tiles.chunk_at(chunk_pos, **chunk_layer) {
+            commands.entity(chunk_id).insert(ChunkModified);
+        }
+    }
+}
+
+fn remove_dug_designations(mut broken: EventReader<TileBroken>, mut commands: Commands) {
+    for &TileBroken { tile, .. } in broken.read() {
+        commands.entity(tile).remove::<DigDesignation>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiles_in_rect() {
+        let tiles: Vec<IVec2> = tiles_in_rect(vec2(60.0, -10.0), vec2(-10.0, 40.0)).collect();
+        assert_eq!(tiles.len(), 6);
+        for x in -1..=1 {
+            for y in -1..=0 {
+                assert!(tiles.contains(&ivec2(x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_tiles_in_rect_single() {
+        let tiles: Vec<IVec2> = tiles_in_rect(vec2(-1.0, -1.0), vec2(-1.0, -1.0)).collect();
+        assert_eq!(tiles, vec![ivec2(-1, -1)]);
+    }
+}
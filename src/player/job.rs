@@ -0,0 +1,430 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::{
+    app::AppUpdate,
+    chunk::ChunkPos,
+    game::PlayState,
+    helper::{
+        move_entity_to::{FollowPath, MoveEntityTo, MoveFinishReason, MoveFinished},
+        pathfinding::Pathfinder,
+    },
+    player::{
+        OwnedBy,
+        dig::{DigDesignation, DigPriority},
+        wisp::PlayerWisp,
+    },
+    terrain::{BrakeTile, TILE_SIZE},
+};
+
+pub struct DigJobPlugin;
+impl Plugin for DigJobPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(queue_dig_job)
+            .add_observer(remove_dig_job)
+            .add_observer(release_dig_job)
+            .add_systems(
+                Update,
+                (drop_interrupted_dig_jobs, assign_dig_jobs, work_dig_jobs)
+                    .chain()
+                    .run_if(in_state(PlayState::Playing))
+                    .in_set(AppUpdate::Action),
+            );
+    }
+}
+
+///A designated tile waiting to be dug
+#[derive(Clone, Copy, Debug)]
+pub struct DigJob {
+    pub tile: Entity,
+    ///Global translation of the center of the tile
+    pub pos: Vec2,
+    pub layer: i32,
+    pub priority: DigPriority,
+    ///The wisp working on it
+    pub reserved_by: Option<Entity>,
+    ///No path led to it so it is left alone until this elapsed time
+    pub retry_at: Option<Duration>,
+}
+
+impl DigJob {
+    ///How long a job nothing could reach waits before wisps look for a path again
+    pub const RETRY_DELAY: Duration = Duration::from_secs(2);
+}
+
+///The dig jobs of a Player
+#[derive(Component, Default)]
+pub struct DigJobQueue(Vec<DigJob>);
+
+impl DigJobQueue {
+    ///Adds the job or updates its priority if the tile is already queued
+    pub fn push(&mut self, job: DigJob) {
+        match self.0.iter_mut().find(|queued| queued.tile == job.tile) {
+            Some(queued) => queued.priority = job.priority,
+            None => self.0.push(job),
+        }
+    }
+
+    pub fn remove(&mut self, tile: Entity) -> Option<DigJob> {
+        let index = self.0.iter().position(|job| job.tile == tile)?;
+        Some(self.0.swap_remove(index))
+    }
+
+    ///Reserves the highest priority free job closest to from
+    ///Jobs waiting to be retried after now are skipped
+    pub fn reserve_best(&mut self, wisp: Entity, from: Vec2, now: Duration) -> Option<DigJob> {
+        let job = self
+            .0
+            .iter_mut()
+            .filter(|job| job.reserved_by.is_none())
+            .filter(|job| job.retry_at.is_none_or(|retry_at| retry_at <= now))
+            .min_by(|a, b| {
+                let distance = |job: &DigJob| job.pos.distance_squared(from);
+                b.priority
+                    .cmp(&a.priority)
+                    .then(distance(a).total_cmp(&distance(b)))
+            })?;
+        job.reserved_by = Some(wisp);
+        Some(*job)
+    }
+
+    ///Frees the job so another wisp can take it
+    pub fn release(&mut self, tile: Entity) {
+        if let Some(job) = self.0.iter_mut().find(|job| job.tile == tile) {
+            job.reserved_by = None;
+        }
+    }
+
+    ///Frees the job and keeps everyone off it until retry_at
+    pub fn postpone(&mut self, tile: Entity, retry_at: Duration) {
+        if let Some(job) = self.0.iter_mut().find(|job| job.tile == tile) {
+            job.reserved_by = None;
+            job.retry_at = Some(retry_at);
+        }
+    }
+}
+
+///How hard and fast a wisp digs
+#[derive(Component, Clone, Copy)]
+pub struct DigPower {
+    pub damage: u32,
+    pub interval: Duration,
+}
+
+impl Default for DigPower {
+    fn default() -> Self {
+        Self {
+            damage: 3,
+            interval: Duration::from_millis(250),
+        }
+    }
+}
+
+///The dig job a wisp is working on
+#[derive(Component)]
+pub struct DigJobAssignment {
+    pub tile: Entity,
+    pub pos: Vec2,
+    timer: Timer,
+}
+
+impl DigJobAssignment {
+    ///How close a wisp has to be to the center of the tile to dig it
+    pub const REACH: f32 = TILE_SIZE.x * 1.5;
+}
+
+fn queue_dig_job(
+    trigger: Trigger<OnInsert, DigDesignation>,
+    tiles: Query<(&DigDesignation, &TilePos, &TilemapId)>,
+    chunks: Query<&ChunkPos>,
+    mut queues: Query<&mut DigJobQueue>,
+) {
+    let tile = trigger.target();
+    let Ok((designation, tile_pos, &TilemapId(chunk_id))) = tiles.get(tile) else {
+        return;
+    };
+    let Ok(chunk_pos) = chunks.get(chunk_id) else {
+        return;
+    };
+    let Ok(mut queue) = queues.get_mut(designation.player) else {
+        warn!("player has no DigJobQueue:{}", designation.player);
+        return;
+    };
+    let local = (UVec2::from(*tile_pos).as_vec2() + 0.5) * TILE_SIZE;
+    queue.push(DigJob {
+        tile,
+        pos: chunk_pos.into_vec3().xy() + local,
+        layer: chunk_pos.z,
+        priority: designation.priority,
+        reserved_by: None,
+        retry_at: None,
+    });
+}
+
+fn remove_dig_job(
+    trigger: Trigger<OnRemove, DigDesignation>,
+    designations: Query<&DigDesignation>,
+    mut queues: Query<&mut DigJobQueue>,
+) {
+    let tile = trigger.target();
+    let Ok(designation) = designations.get(tile) else {
+        return;
+    };
+    if let Ok(mut queue) = queues.get_mut(designation.player) {
+        queue.remove(tile);
+    }
+}
+
+///Frees the job when a wisp stops working on it for any reason
+fn release_dig_job(
+    trigger: Trigger<OnRemove, DigJobAssignment>,
+    wisps: Query<(&DigJobAssignment, &OwnedBy)>,
+    mut queues: Query<&mut DigJobQueue>,
+) {
+    let Ok((assignment, &OwnedBy(player))) = wisps.get(trigger.target()) else {
+        return;
+    };
+    if let Ok(mut queue) = queues.get_mut(player) {
+        queue.release(assignment.tile);
+    }
+}
+
+///A wisp that got halted or sent somewhere else won't reach its job so it lets go of it
+fn drop_interrupted_dig_jobs(
+    mut finished: EventReader<MoveFinished>,
+    wisps: Query<(), With<DigJobAssignment>>,
+    mut commands: Commands,
+) {
+    for &MoveFinished { entity, reason } in finished.read() {
+        if reason != MoveFinishReason::Arrived && wisps.contains(entity) {
+            commands.entity(entity).remove::<DigJobAssignment>();
+        }
+    }
+}
+
+fn assign_dig_jobs(
+    time: Res<Time>,
+    mut wisps: Query<
+        (Entity, &GlobalTransform, &OwnedBy, &DigPower),
+        (With<PlayerWisp>, Without<DigJobAssignment>),
+    >,
+    mut queues: Query<&mut DigJobQueue>,
+    mut pathfinder: Pathfinder,
+    mut commands: Commands,
+) {
+    for (wisp, transform, &OwnedBy(player), power) in wisps.iter_mut() {
+        let Ok(mut queue) = queues.get_mut(player) else {
+            continue;
+        };
+        let from = transform.translation().xy();
+        let Some(job) = queue.reserve_best(wisp, from, time.elapsed()) else {
+            continue;
+        };
+        //Walled in for now, digging around it might open a way
+        let Some(waypoints) = pathfinder.find_path_next_to(from, job.pos, job.layer) else {
+            queue.postpone(job.tile, time.elapsed() + DigJob::RETRY_DELAY);
+            continue;
+        };
+        commands
+            .entity(wisp)
+            .insert(DigJobAssignment {
+                tile: job.tile,
+                pos: job.pos,
+                timer: Timer::new(power.interval, TimerMode::Repeating),
+            })
+            .remove::<MoveEntityTo>()
+            .insert(FollowPath::new(
+                from,
+                waypoints,
+                EaseFunction::SmootherStepIn,
+            ));
+    }
+}
+
+fn work_dig_jobs(
+    time: Res<Time>,
+    mut wisps: Query<(
+        Entity,
+        &GlobalTransform,
+        &mut DigJobAssignment,
+        &DigPower,
+        Has<MoveEntityTo>,
+        Has<FollowPath>,
+    )>,
+    designations: Query<(), With<DigDesignation>>,
+    mut brakes: EventWriter<BrakeTile>,
+    mut commands: Commands,
+) {
+    for (wisp, transform, mut assignment, power, moving, following) in wisps.iter_mut() {
+        //Dug or cancelled so go back to idle
+        if designations.get(assignment.tile).is_err() {
+            commands
+                .entity(wisp)
+                .remove::<(DigJobAssignment, MoveEntityTo, FollowPath)>();
+            continue;
+        }
+        let distance = transform.translation().xy().distance(assignment.pos);
+        if distance > DigJobAssignment::REACH {
+            //Stopped short of it, dropping the job lets it get a new path
+            if !moving && !following {
+                commands.entity(wisp).remove::<DigJobAssignment>();
+            }
+            continue;
+        }
+        if assignment.timer.tick(time.delta()).just_finished() {
+            brakes.write(BrakeTile::ByEntity {
+                tile: assignment.tile,
+                damage: power.damage,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(tile: Entity, pos: Vec2, priority: DigPriority) -> DigJob {
+        DigJob {
+            tile,
+            pos,
+            layer: 0,
+            priority,
+            reserved_by: None,
+            retry_at: None,
+        }
+    }
+
+    #[test]
+    fn test_reserve_best_priority_then_distance() {
+        let [near, far, high] = [1, 2, 3].map(Entity::from_raw);
+        let wisp = Entity::from_raw(10);
+        let mut queue = DigJobQueue::default();
+        queue.push(job(far, vec2(500.0, 0.0), DigPriority::Normal));
+        queue.push(job(near, vec2(50.0, 0.0), DigPriority::Normal));
+        queue.push(job(high, vec2(1000.0, 0.0), DigPriority::High));
+
+        assert_eq!(
+            queue
+                .reserve_best(wisp, Vec2::ZERO, Duration::ZERO)
+                .unwrap()
+                .tile,
+            high
+        );
+        assert_eq!(
+            queue
+                .reserve_best(wisp, Vec2::ZERO, Duration::ZERO)
+                .unwrap()
+                .tile,
+            near
+        );
+        assert_eq!(
+            queue
+                .reserve_best(wisp, Vec2::ZERO, Duration::ZERO)
+                .unwrap()
+                .tile,
+            far
+        );
+        //Everything is reserved
+        assert!(
+            queue
+                .reserve_best(wisp, Vec2::ZERO, Duration::ZERO)
+                .is_none()
+        );
+
+        queue.release(near);
+        assert_eq!(
+            queue
+                .reserve_best(wisp, Vec2::ZERO, Duration::ZERO)
+                .unwrap()
+                .tile,
+            near
+        );
+    }
+
+    #[test]
+    fn test_postponed_job_waits() {
+        let [blocked, open] = [1, 2].map(Entity::from_raw);
+        let wisp = Entity::from_raw(10);
+        let mut queue = DigJobQueue::default();
+        queue.push(job(blocked, vec2(50.0, 0.0), DigPriority::High));
+        queue.push(job(open, vec2(500.0, 0.0), DigPriority::Normal));
+
+        let now = Duration::from_secs(5);
+        assert_eq!(
+            queue.reserve_best(wisp, Vec2::ZERO, now).unwrap().tile,
+            blocked
+        );
+        queue.postpone(blocked, now + DigJob::RETRY_DELAY);
+        assert_eq!(
+            queue.reserve_best(wisp, Vec2::ZERO, now).unwrap().tile,
+            open
+        );
+        assert!(queue.reserve_best(wisp, Vec2::ZERO, now).is_none());
+
+        let later = now + DigJob::RETRY_DELAY;
+        assert_eq!(
+            queue.reserve_best(wisp, Vec2::ZERO, later).unwrap().tile,
+            blocked
+        );
+    }
+
+    #[test]
+    fn test_push_updates_priority() {
+        let tile = Entity::from_raw(1);
+        let mut queue = DigJobQueue::default();
+        queue.push(job(tile, Vec2::ZERO, DigPriority::Low));
+        queue.push(job(tile, Vec2::ZERO, DigPriority::High));
+        let job = queue.remove(tile).unwrap();
+        assert_eq!(job.priority, DigPriority::High);
+        //It was only queued once
+        assert!(queue.remove(tile).is_none());
+    }
+
+    #[test]
+    fn test_interrupted_move_drops_job() {
+        let mut app = App::new();
+        app.add_event::<MoveFinished>()
+            .add_observer(release_dig_job)
+            .add_systems(Update, drop_interrupted_dig_jobs);
+        let tile = Entity::from_raw(1);
+        let mut queue = DigJobQueue::default();
+        queue.push(job(tile, Vec2::ZERO, DigPriority::Normal));
+        let player = app.world_mut().spawn(queue).id();
+        let wisp = app.world_mut().spawn(OwnedBy(player)).id();
+        let reserved = app
+            .world_mut()
+            .get_mut::<DigJobQueue>(player)
+            .unwrap()
+            .reserve_best(wisp, Vec2::ZERO, Duration::ZERO);
+        assert!(reserved.is_some());
+        app.world_mut().entity_mut(wisp).insert(DigJobAssignment {
+            tile,
+            pos: Vec2::ZERO,
+            timer: Timer::default(),
+        });
+
+        let finish = |app: &mut App, reason: MoveFinishReason| {
+            app.world_mut().send_event(MoveFinished {
+                entity: wisp,
+                reason,
+            });
+            app.update();
+        };
+        //Arriving is how a wisp gets to its job
+        finish(&mut app, MoveFinishReason::Arrived);
+        assert!(app.world().get::<DigJobAssignment>(wisp).is_some());
+
+        finish(&mut app, MoveFinishReason::Halted);
+        assert!(app.world().get::<DigJobAssignment>(wisp).is_none());
+        //Free for the next wisp
+        let mut queue = app.world_mut().get_mut::<DigJobQueue>(player).unwrap();
+        assert!(
+            queue
+                .reserve_best(wisp, Vec2::ZERO, Duration::ZERO)
+                .is_some()
+        );
+    }
+}
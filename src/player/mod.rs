@@ -2,8 +2,11 @@ use bevy::prelude::*;
 
 use crate::app::AppState;
 
+pub mod bookmark;
 mod command;
 pub mod core;
+pub mod dig;
+pub mod job;
 pub mod view;
 pub mod wisp;
 
@@ -14,19 +17,35 @@ impl Plugin for PlayerPlugin {
             core::PlayerCorePlugin,
             wisp::PlayerWispPlugin,
             view::PlayerViewPlugin,
+            dig::DigPlugin,
+            job::DigJobPlugin,
+            bookmark::CameraBookmarkPlugin,
         ));
         app.add_systems(OnEnter(AppState::Game), spawn_player);
     }
 }
 
 #[derive(Component, Default)]
+#[require(job::DigJobQueue, bookmark::CameraBookmarks)]
 pub struct Player;
 
-#[derive(Component, Default, Deref, DerefMut)]
+#[derive(Component, Default, Deref, DerefMut, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct PlayerId(pub u8);
+impl PlayerId {
+    const COLORS: [Srgba; 4] = [
+        bevy::color::palettes::tailwind::SKY_500,
+        bevy::color::palettes::tailwind::RED_500,
+        bevy::color::palettes::tailwind::EMERALD_500,
+        bevy::color::palettes::tailwind::AMBER_500,
+    ];
+
+    pub fn color(&self) -> Color {
+        Self::COLORS[self.0 as usize % Self::COLORS.len()].into()
+    }
+}
 
 fn spawn_player(mut commands: Commands) {
-    commands.spawn(Player);
+    commands.spawn((Player, PlayerId(0)));
 }
 
 #[derive(Component)]
@@ -1,19 +1,29 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
 use crate::{
+    app::AppUpdate,
     camera::MainCamera,
-    helper::move_entity_to::{MoveEntityTo, Speed},
-    player::{OwnedBy, Player},
+    helper::move_entity_to::{MoveEntityTo, MoveMode, MovesWhilePaused},
+    input::{ActionMap, InputAction, InputActionAppExt, InputBinding},
+    player::{OwnedBy, Player, wisp::PlayerWisp},
+    terrain::TILE_SIZE,
 };
 
 pub struct PlayerViewPlugin;
 impl Plugin for PlayerViewPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<MovePlayerView>()
+            .register_input_action(InputAction::FollowWisp, [InputBinding::key(KeyCode::KeyF)])
             .add_observer(spawn_default_player_view)
             .add_observer(move_active_player_view)
             .add_observer(move_player_view_to)
-            .add_observer(move_player_view_by);
+            .add_observer(move_player_view_by)
+            .add_observer(follow_with_player_view)
+            .add_observer(follow_own_wisp)
+            .add_systems(Update, follow_wisp_on_action.in_set(AppUpdate::PreData))
+            .add_systems(Update, follow_target.in_set(AppUpdate::Action));
     }
 }
 
@@ -31,7 +41,6 @@ fn spawn_default_player_view(
             PlayerView,
             camera_transform.compute_transform(),
             OwnedBy(trigger.target()),
-            Speed(10000.0),
         ))
         //Adding the camera too sead view
         .add_child(camera);
@@ -40,21 +49,65 @@ fn spawn_default_player_view(
 }
 
 #[derive(Component, Default)]
-#[require(Transform)]
+#[require(Transform, MovesWhilePaused)]
 pub struct PlayerView;
 
+impl PlayerView {
+    ///How long MovePlayerView::To takes however far it goes
+    pub const MOVE_DURATION: Duration = Duration::from_millis(400);
+}
+
+///By is manual panning and stops any FollowTarget
 #[derive(Event)]
 pub enum MovePlayerView {
     To(Vec2),
     By(Vec2),
+    Follow(Entity),
 }
 
 #[derive(Event)]
 pub enum MoveActivePlayerView {
     To(Vec2),
     By(Vec2),
+    Follow(Entity),
+}
+
+///Keeps a PlayerView on an entity
+#[derive(Component, Clone, Copy)]
+pub struct FollowTarget {
+    pub target: Entity,
+    ///How quickly the view catches up, higher is snappier
+    pub smoothing: f32,
+    ///How far the target can get from the center before the view moves
+    pub deadzone: f32,
+}
+
+impl FollowTarget {
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            smoothing: 5.0,
+            deadzone: TILE_SIZE.x * 2.0,
+        }
+    }
+
+    ///Where the view should be delta_secs after being at view with the target at target
+    pub fn step(&self, view: Vec2, target: Vec2, delta_secs: f32) -> Vec2 {
+        let offset = target - view;
+        let distance = offset.length();
+        if distance <= self.deadzone {
+            return view;
+        }
+        //Just enough to get the target back on the edge of the deadzone
+        let wanted = target - offset / distance * self.deadzone;
+        view.lerp(wanted, 1.0 - (-self.smoothing * delta_secs).exp())
+    }
 }
 
+///Makes the active view follow the Player's wisp
+#[derive(Event)]
+pub struct FollowOwnWisp;
+
 fn move_active_player_view(
     trigger: Trigger<MoveActivePlayerView>,
     mut commands: Commands,
@@ -64,6 +117,7 @@ fn move_active_player_view(
         let move_type = match trigger.event() {
             MoveActivePlayerView::To(amount) => MovePlayerView::To(*amount),
             MoveActivePlayerView::By(amount) => MovePlayerView::By(*amount),
+            MoveActivePlayerView::Follow(target) => MovePlayerView::Follow(*target),
         };
         commands.entity(parent.parent()).trigger(move_type);
     }
@@ -80,16 +134,23 @@ fn move_player_view_to(
     let Ok((transform, move_entity_to)) = transforms.get_mut(target) else {
         return;
     };
+    commands.entity(target).remove::<FollowTarget>();
+    let from = transform.translation().xy();
     match move_entity_to {
         Some(mut move_entity_to) => {
-            move_entity_to.to += to;
+            move_entity_to.from = from;
+            move_entity_to.to = to;
+            //Starts over from where it is or it would jump ahead along the curve
+            if let MoveMode::Timed(timer) = &mut move_entity_to.mode {
+                timer.reset();
+            }
         }
         None => {
-            let from = transform.translation().xy();
             commands.entity(target).insert(MoveEntityTo {
                 to,
                 from,
-                easing: EaseFunction::SmootherStepIn,
+                easing: EaseFunction::SmootherStep,
+                mode: MoveMode::timed(PlayerView::MOVE_DURATION),
             });
         }
     }
@@ -98,9 +159,116 @@ fn move_player_view_to(
 fn move_player_view_by(
     trigger: Trigger<MovePlayerView>,
     mut transforms: Query<&mut Transform, With<PlayerView>>,
+    mut commands: Commands,
 ) {
     let &MovePlayerView::By(amout) = trigger.event() else {
         return;
     };
     transforms.get_mut(trigger.target()).unwrap().translation += amout.extend(0.0);
+    commands.entity(trigger.target()).remove::<FollowTarget>();
+}
+
+fn follow_with_player_view(trigger: Trigger<MovePlayerView>, mut commands: Commands) {
+    let &MovePlayerView::Follow(target) = trigger.event() else {
+        return;
+    };
+    commands
+        .entity(trigger.target())
+        .remove::<MoveEntityTo>()
+        .insert(FollowTarget::new(target));
+}
+
+fn follow_own_wisp(
+    _trigger: Trigger<FollowOwnWisp>,
+    player: Query<Entity, With<Player>>,
+    wisps: Query<(Entity, &OwnedBy), With<PlayerWisp>>,
+    mut commands: Commands,
+) {
+    let Ok(player) = player.single() else {
+        return;
+    };
+    if let Some((wisp, _)) = wisps.iter().find(|&(_, &OwnedBy(owner))| owner == player) {
+        commands.trigger(MoveActivePlayerView::Follow(wisp));
+    }
+}
+
+fn follow_wisp_on_action(actions: Res<ActionMap>, mut commands: Commands) {
+    if actions.just_pressed(InputAction::FollowWisp) {
+        commands.trigger(FollowOwnWisp);
+    }
+}
+
+fn follow_target(
+    time: Res<Time>,
+    mut views: Query<(Entity, &mut Transform, &FollowTarget), With<PlayerView>>,
+    targets: Query<&GlobalTransform>,
+    mut commands: Commands,
+) {
+    for (view, mut transform, follow) in views.iter_mut() {
+        let Ok(target) = targets.get(follow.target) else {
+            //Nothing left to follow
+            commands.entity(view).remove::<FollowTarget>();
+            continue;
+        };
+        let pos = transform.translation.xy();
+        let next = follow.step(pos, target.translation().xy(), time.delta_secs());
+        if next != pos {
+            transform.translation = next.extend(transform.translation.z);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_target_step() {
+        let follow = FollowTarget {
+            target: Entity::PLACEHOLDER,
+            smoothing: 5.0,
+            deadzone: 10.0,
+        };
+        //Inside the deadzone the view stays put
+        assert_eq!(follow.step(Vec2::ZERO, vec2(6.0, 8.0), 1.0), Vec2::ZERO);
+        //Catches up toward the edge of the deadzone but never past it
+        let next = follow.step(Vec2::ZERO, vec2(110.0, 0.0), 0.1);
+        assert!(next.x > 0.0 && next.x < 100.0 && next.y == 0.0);
+        let settled = follow.step(Vec2::ZERO, vec2(110.0, 0.0), 100.0);
+        assert!((settled - vec2(100.0, 0.0)).length() < 1e-3);
+    }
+
+    #[test]
+    fn test_retarget_restarts_timed_move() {
+        let mut app = App::new();
+        app.add_observer(move_player_view_to);
+        let mut mode = MoveMode::timed(PlayerView::MOVE_DURATION);
+        if let MoveMode::Timed(timer) = &mut mode {
+            timer.tick(PlayerView::MOVE_DURATION / 2);
+        }
+        let view = app
+            .world_mut()
+            .spawn((
+                PlayerView,
+                GlobalTransform::from_xyz(50.0, 0.0, 0.0),
+                MoveEntityTo {
+                    to: vec2(100.0, 0.0),
+                    from: Vec2::ZERO,
+                    easing: EaseFunction::SmootherStep,
+                    mode,
+                },
+            ))
+            .id();
+
+        app.world_mut()
+            .trigger_targets(MovePlayerView::To(vec2(0.0, 200.0)), view);
+        app.world_mut().flush();
+        let move_to = app.world().get::<MoveEntityTo>(view).unwrap();
+        assert_eq!(move_to.from, vec2(50.0, 0.0));
+        assert_eq!(move_to.to, vec2(0.0, 200.0));
+        let MoveMode::Timed(timer) = &move_to.mode else {
+            panic!("view moves are timed");
+        };
+        assert_eq!(timer.elapsed(), Duration::ZERO);
+    }
 }
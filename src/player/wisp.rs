@@ -1,8 +1,11 @@
 use crate::app::AppUpdate;
 use crate::cursor::CurrsorPositon;
-use crate::helper::move_entity_to::{Halt, MoveEntityTo, Speed, on_halt};
+use crate::game::PlayState;
+use crate::helper::move_entity_to::{Halt, MoveEntityTo, MoveMode, Speed, on_halt};
+use crate::helper::steering::Steering;
 use crate::player::OwnedBy;
 use crate::player::core::PlayerCore;
+use crate::player::job::{DigJobAssignment, DigPower};
 use bevy::prelude::*;
 use bevy_asset_loader::asset_collection::AssetCollection;
 
@@ -14,16 +17,16 @@ impl Plugin for PlayerWispPlugin {
             Update,
             (update_move_to, add_move_to)
                 .chain()
+                .run_if(in_state(PlayState::Playing))
                 .in_set(AppUpdate::Data),
         );
         app.add_observer(on_halt::<HomeToCursor>)
-            .add_observer(on_halt::<Halt>)
-            .add_observer(on_halt::<MoveEntityTo>);
+            .add_observer(on_halt::<Halt>);
     }
 }
 
 #[derive(Component, Default)]
-#[require(Transform)]
+#[require(Transform, DigPower, Steering)]
 pub struct PlayerWisp;
 
 #[derive(AssetCollection, Resource)]
@@ -56,7 +59,15 @@ fn spawn_player_wisp(
 fn add_move_to(
     mut commands: Commands,
     cursor_pos: Res<CurrsorPositon>,
-    wisp_q: Query<(&GlobalTransform, Entity), (With<PlayerWisp>, With<HomeToCursor>)>,
+    wisp_q: Query<
+        (&GlobalTransform, Entity),
+        (
+            With<PlayerWisp>,
+            With<HomeToCursor>,
+            Without<DigJobAssignment>,
+            Without<MoveEntityTo>,
+        ),
+    >,
 ) {
     for (wisp_transform, entity) in wisp_q.iter() {
         let wisp_pos = wisp_transform.translation().xy();
@@ -65,13 +76,21 @@ fn add_move_to(
                 to: **cursor_pos,
                 from: wisp_pos,
                 easing: EaseFunction::SmootherStepIn,
+                mode: MoveMode::Speed,
             });
         }
     }
 }
 
 fn update_move_to(
-    mut wisp_q: Query<&mut MoveEntityTo, (With<PlayerWisp>, With<HomeToCursor>)>,
+    mut wisp_q: Query<
+        &mut MoveEntityTo,
+        (
+            With<PlayerWisp>,
+            With<HomeToCursor>,
+            Without<DigJobAssignment>,
+        ),
+    >,
     cursor_pos: Res<CurrsorPositon>,
 ) {
     for mut move_to in wisp_q.iter_mut() {
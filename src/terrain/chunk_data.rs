@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use super::{TILE_COUNT, TILES_PRE_CHUNK, tile_index};
+use crate::{
+    player::{PlayerId, dig::DigPriority},
+    terrain::{
+        generation::WorldGenSettings,
+        tile_data::{TerrainType, TileType},
+    },
+};
+
+///The tile grid of one chunk
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkData {
+    pub tiles: [TileType; TILE_COUNT],
+    pub terrain: [TerrainType; TILE_COUNT],
+    ///TileHealth of every tile
+    pub health: [u32; TILE_COUNT],
+    ///Who marked the tile for digging and with what priority
+    pub digs: [Option<(PlayerId, DigPriority)>; TILE_COUNT],
+}
+
+impl Default for ChunkData {
+    fn default() -> Self {
+        Self {
+            tiles: [TileType::default(); TILE_COUNT],
+            terrain: [TerrainType::default(); TILE_COUNT],
+            health: [TerrainType::default().hardness(); TILE_COUNT],
+            digs: [None; TILE_COUNT],
+        }
+    }
+}
+
+impl ChunkData {
+    ///Bytes taken up by one chunk when serialized
+    ///Tile and terrain take one byte, health four and the dig two
+    pub const BYTES: usize = TILE_COUNT * 8;
+
+    ///Generates a fresh chunk
+    pub fn generate(chunk_pos: IVec3, settings: &WorldGenSettings) -> Self {
+        let mut data = Self::default();
+        for x in 0..TILES_PRE_CHUNK.x {
+            for y in 0..TILES_PRE_CHUNK.y {
+                data.set(
+                    UVec2::new(x, y),
+                    TileType::generate(x, y, chunk_pos, settings),
+                    TerrainType::generate(x, y, chunk_pos, settings),
+                );
+            }
+        }
+        data
+    }
+
+    pub fn get(&self, tile_pos: UVec2) -> (TileType, TerrainType) {
+        let index = tile_index(tile_pos.x, tile_pos.y) as usize;
+        (self.tiles[index], self.terrain[index])
+    }
+
+    ///Sets a fresh tile at full health with no dig
+    pub fn set(&mut self, tile_pos: UVec2, tile_type: TileType, terrain_type: TerrainType) {
+        let index = tile_index(tile_pos.x, tile_pos.y) as usize;
+        self.tiles[index] = tile_type;
+        self.terrain[index] = terrain_type;
+        self.health[index] = terrain_type.hardness();
+        self.digs[index] = None;
+    }
+
+    pub fn health(&self, tile_pos: UVec2) -> u32 {
+        self.health[tile_index(tile_pos.x, tile_pos.y) as usize]
+    }
+
+    pub fn dig(&self, tile_pos: UVec2) -> Option<(PlayerId, DigPriority)> {
+        self.digs[tile_index(tile_pos.x, tile_pos.y) as usize]
+    }
+
+    ///Writes the tiles, the terrain, the health(u32 le) and then the digs
+    ///A dig is the player id + 1 (0 for no dig) and the priority
+    pub fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.extend(self.tiles.iter().map(|&tile| tile as u8));
+        out.extend(self.terrain.iter().map(|&terrain| terrain as u8));
+        out.extend(self.health.iter().flat_map(|health| health.to_le_bytes()));
+        out.extend(self.digs.iter().flat_map(|dig| match dig {
+            Some((PlayerId(player), priority)) => [player + 1, *priority as u8],
+            None => [0, 0],
+        }));
+    }
+
+    ///Reads what write_bytes wrote
+    ///Returns None if the bytes are too short or hold an unknown type
+    pub fn read_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < Self::BYTES {
+            return None;
+        }
+        let (tiles, rest) = bytes[..Self::BYTES].split_at(TILE_COUNT);
+        let (terrain, rest) = rest.split_at(TILE_COUNT);
+        let (health, digs) = rest.split_at(TILE_COUNT * 4);
+        let mut data = Self::default();
+        for index in 0..TILE_COUNT {
+            data.tiles[index] = TileType::from_repr(tiles[index])?;
+            data.terrain[index] = TerrainType::from_repr(terrain[index])?;
+            let health = &health[index * 4..index * 4 + 4];
+            data.health[index] = u32::from_le_bytes(health.try_into().ok()?);
+            let &[player, priority] = &digs[index * 2..index * 2 + 2] else {
+                return None;
+            };
+            data.digs[index] = match player {
+                0 => None,
+                _ => Some((PlayerId(player - 1), DigPriority::from_repr(priority)?)),
+            };
+        }
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_data_bytes_round_trip() {
+        let mut data = ChunkData::default();
+        data.set(uvec2(0, 0), TileType::Ground, TerrainType::Sand);
+        data.set(uvec2(9, 3), TileType::Ground, TerrainType::Dirt);
+        let index = tile_index(5u32, 5) as usize;
+        data.health[index] = 7;
+        data.digs[index] = Some((PlayerId(2), DigPriority::High));
+
+        let mut bytes = Vec::new();
+        data.write_bytes(&mut bytes);
+        assert_eq!(bytes.len(), ChunkData::BYTES);
+
+        let read = ChunkData::read_bytes(&bytes).unwrap();
+        assert_eq!(read, data);
+        assert_eq!(read.get(uvec2(9, 3)), (TileType::Ground, TerrainType::Dirt));
+        assert_eq!(read.health(uvec2(5, 5)), 7);
+        assert_eq!(
+            read.dig(uvec2(5, 5)),
+            Some((PlayerId(2), DigPriority::High))
+        );
+        assert_eq!(read.dig(uvec2(9, 3)), None);
+    }
+
+    #[test]
+    fn test_chunk_data_rejects_bad_bytes() {
+        assert!(ChunkData::read_bytes(&[0; ChunkData::BYTES - 1]).is_none());
+        assert!(ChunkData::read_bytes(&[u8::MAX; ChunkData::BYTES]).is_none());
+    }
+}
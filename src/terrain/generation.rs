@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+
+use super::TILES_PRE_CHUNK;
+use crate::terrain::tile_data::{TerrainType, TileType};
+
+///Settings for generating the world
+///The same settings always generate the same world
+#[derive(Resource, Clone, Debug)]
+pub struct WorldGenSettings {
+    pub seed: u32,
+    ///Octaves of noise layered on top of each other
+    pub octaves: u32,
+    ///Size in tiles of the cave tunnels
+    pub cave_scale: f32,
+    ///How close to the middle of a tunnel a tile has to be to be opened (0..1)
+    pub cave_threshold: f32,
+    ///Size in tiles of the open pockets
+    pub pocket_scale: f32,
+    ///Noise value above which a tile is in a pocket (0..1)
+    pub pocket_threshold: f32,
+    ///Size in tiles of the Stone/Dirt/Sand bands
+    pub terrain_scale: f32,
+    ///Noise values below the first are Stone, below the second Dirt, above Sand
+    pub terrain_bands: [f32; 2],
+}
+
+impl Default for WorldGenSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0x5eed,
+            octaves: 3,
+            cave_scale: 24.0,
+            cave_threshold: 0.96,
+            pocket_scale: 12.0,
+            pocket_threshold: 0.75,
+            terrain_scale: 40.0,
+            terrain_bands: [0.45, 0.6],
+        }
+    }
+}
+
+impl WorldGenSettings {
+    //Offsets the seed per noise layer so they don't line up
+    const CAVE_SEED: u32 = 0;
+    const POCKET_SEED: u32 = 1000;
+    const TERRAIN_SEED: u32 = 2000;
+
+    ///Converts a tile in a chunk to its global tile coordinate
+    pub fn global_tile(x: u32, y: u32, chunk_pos: IVec3) -> IVec2 {
+        chunk_pos.xy() * TILES_PRE_CHUNK.as_ivec2() + ivec2(x as i32, y as i32)
+    }
+
+    fn layer_seed(&self, layer: i32, offset: u32) -> u32 {
+        self.seed
+            .wrapping_add(hash(layer, 0, self.seed))
+            .wrapping_add(offset)
+    }
+
+    pub fn tile_type_at(&self, tile: IVec2, layer: i32) -> TileType {
+        let point = tile.as_vec2();
+
+        //Ridged noise gives long winding tunnels
+        let cave = fbm(
+            point / self.cave_scale,
+            self.layer_seed(layer, Self::CAVE_SEED),
+            self.octaves,
+        );
+        let ridge = 1.0 - (cave * 2.0 - 1.0).abs();
+
+        let pocket = fbm(
+            point / self.pocket_scale,
+            self.layer_seed(layer, Self::POCKET_SEED),
+            self.octaves,
+        );
+
+        if ridge > self.cave_threshold || pocket > self.pocket_threshold {
+            TileType::Ground
+        } else {
+            TileType::Wall
+        }
+    }
+
+    pub fn terrain_type_at(&self, tile: IVec2, layer: i32) -> TerrainType {
+        let band = fbm(
+            tile.as_vec2() / self.terrain_scale,
+            self.layer_seed(layer, Self::TERRAIN_SEED),
+            self.octaves,
+        );
+        let [stone, dirt] = self.terrain_bands;
+        if band < stone {
+            TerrainType::Stone
+        } else if band < dirt {
+            TerrainType::Dirt
+        } else {
+            TerrainType::Sand
+        }
+    }
+}
+
+///Integer hash of a lattice point
+fn hash(x: i32, y: i32, seed: u32) -> u32 {
+    let mut h = seed ^ (x as u32).wrapping_mul(0x27d4_eb2d) ^ (y as u32).wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
+
+///Smoothly interpolated random values on an integer lattice (0..1)
+pub fn value_noise(point: Vec2, seed: u32) -> f32 {
+    let cell = point.floor();
+    let local = point - cell;
+    let IVec2 { x, y } = cell.as_ivec2();
+    let corner = |dx: i32, dy: i32| hash(x + dx, y + dy, seed) as f32 / u32::MAX as f32;
+
+    let t = local * local * (3.0 - 2.0 * local);
+    let bottom = corner(0, 0).lerp(corner(1, 0), t.x);
+    let top = corner(0, 1).lerp(corner(1, 1), t.x);
+    bottom.lerp(top, t.y)
+}
+
+///Layers octaves of value_noise each at double the frequency and half the strength (0..1)
+pub fn fbm(point: Vec2, seed: u32, octaves: u32) -> f32 {
+    let mut total = 0.0;
+    let mut strength = 1.0;
+    let mut max = 0.0;
+    let mut frequency = 1.0;
+    for octave in 0..octaves.max(1) {
+        total += value_noise(point * frequency, seed.wrapping_add(octave)) * strength;
+        max += strength;
+        strength *= 0.5;
+        frequency *= 2.0;
+    }
+    total / max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::chunk_data::ChunkData;
+
+    #[test]
+    fn test_same_seed_same_chunk() {
+        let settings = WorldGenSettings::default();
+        for chunk_pos in [ivec3(0, 0, 0), ivec3(-3, 7, 0), ivec3(12, -40, 1)] {
+            assert_eq!(
+                ChunkData::generate(chunk_pos, &settings),
+                ChunkData::generate(chunk_pos, &settings.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_different_seed_different_world() {
+        let a = WorldGenSettings::default();
+        let b = WorldGenSettings {
+            seed: a.seed + 1,
+            ..default()
+        };
+        let differs = (-4..4).any(|x| {
+            let chunk_pos = ivec3(x, 0, 0);
+            ChunkData::generate(chunk_pos, &a) != ChunkData::generate(chunk_pos, &b)
+        });
+        assert!(differs);
+    }
+
+    #[test]
+    fn test_world_has_variety() {
+        let settings = WorldGenSettings::default();
+        let chunks: Vec<ChunkData> = (-5..5)
+            .flat_map(|x| (-5..5).map(move |y| ivec3(x, y, 0)))
+            .map(|chunk_pos| ChunkData::generate(chunk_pos, &settings))
+            .collect();
+        let has_tile = |tile: TileType| chunks.iter().any(|c| c.tiles.contains(&tile));
+        let has_terrain =
+            |terrain: TerrainType| chunks.iter().any(|c| c.terrain.contains(&terrain));
+        assert!(has_tile(TileType::Wall));
+        assert!(has_tile(TileType::Ground));
+        assert!(has_terrain(TerrainType::Stone));
+        assert!(has_terrain(TerrainType::Dirt));
+        assert!(has_terrain(TerrainType::Sand));
+    }
+
+    #[test]
+    fn test_global_tile_across_chunk_border() {
+        assert_eq!(
+            WorldGenSettings::global_tile(9, 0, ivec3(0, 0, 0)),
+            ivec2(9, 0)
+        );
+        assert_eq!(
+            WorldGenSettings::global_tile(0, 0, ivec3(1, 0, 0)),
+            ivec2(10, 0)
+        );
+        assert_eq!(
+            WorldGenSettings::global_tile(9, 9, ivec3(-1, -1, 0)),
+            ivec2(-1, -1)
+        );
+    }
+
+    #[test]
+    fn test_noise_is_continuous() {
+        //No jumps between lattice cells so chunk borders are seamless
+        for i in -50..50 {
+            let point = vec2(i as f32 * 0.37, i as f32 * -0.21);
+            let step = vec2(0.001, 0.001);
+            let diff = (fbm(point, 7, 3) - fbm(point + step, 7, 3)).abs();
+            assert!(diff < 0.05, "noise jumped by {diff} at {point}");
+        }
+    }
+}
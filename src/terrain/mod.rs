@@ -5,15 +5,29 @@ use bevy_asset_loader::asset_collection::AssetCollection;
 
 use crate::chunk::Chunk;
 
+mod chunk_data;
+mod generation;
+mod persistence;
+mod picking;
 mod tile_data;
 mod tilemap;
 
-pub use tile_data::{BrakeTile, brake_all_tiles_around};
+pub use generation::WorldGenSettings;
+pub use persistence::ChunkModified;
+pub use tile_data::{
+    BrakeTile, TerrainType, TileBroken, TileGrid, TileType, brake_all_tiles_around,
+};
 
 pub struct TerrainPlugin;
 impl Plugin for TerrainPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((tile_data::TerrainDataPlugin, tilemap::TerrainTilemapPlugin));
+        app.init_resource::<WorldGenSettings>();
+        app.add_plugins((
+            tile_data::TerrainDataPlugin,
+            tilemap::TerrainTilemapPlugin,
+            persistence::TerrainPersistencePlugin,
+            picking::TilePickingPlugin,
+        ));
     }
 }
 
@@ -23,6 +37,10 @@ pub const TILE_SIZE: Vec2 = Vec2::new(
     Chunk::SIZE.x / TILES_PRE_CHUNK.x as f32,
     Chunk::SIZE.y / TILES_PRE_CHUNK.y as f32,
 );
+///Z of the DomainTints above a layer's tilemap, below units
+pub const DOMAIN_TINT_Z: f32 = 0.5;
+///Z of the TileHighlight, above the DomainTints so the two don't z-fight
+pub const TILE_HIGHLIGHT_Z: f32 = 0.6;
 
 pub fn tile_index<T>(x: T, y: T) -> T
 where
@@ -32,8 +50,76 @@ where
     y * tpc_x + x
 }
 
+///Global tile coordinate of the tile at the global translation
+pub fn global_tile_at(pos: Vec2) -> IVec2 {
+    (pos / TILE_SIZE).floor().as_ivec2()
+}
+
+///Global translation of the center of the tile
+pub fn global_tile_center(tile: IVec2) -> Vec2 {
+    (tile.as_vec2() + 0.5) * TILE_SIZE
+}
+
+///Splits a global tile coordinate into its chunk and its position in that chunk
+pub fn split_global_tile(tile: IVec2) -> (IVec2, UVec2) {
+    let size = TILES_PRE_CHUNK.as_ivec2();
+    (tile.div_euclid(size), tile.rem_euclid(size).as_uvec2())
+}
+
+///Chunk and position in that chunk of the tile at the global translation
+pub fn split_global_pos(pos: Vec2) -> (IVec2, UVec2) {
+    split_global_tile(global_tile_at(pos))
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct TerrainTileAtlas {
     #[asset(path = "tile_map.png")]
     pub texture: Handle<Image>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_tile_negative() {
+        assert_eq!(global_tile_at(vec2(0.0, 49.9)), ivec2(0, 0));
+        assert_eq!(global_tile_at(vec2(-0.1, -50.0)), ivec2(-1, -1));
+        assert_eq!(global_tile_at(vec2(-50.1, 500.0)), ivec2(-2, 10));
+        assert_eq!(global_tile_center(ivec2(-1, 2)), vec2(-25.0, 125.0));
+    }
+
+    #[test]
+    fn test_split_global_tile() {
+        assert_eq!(split_global_tile(ivec2(0, 9)), (ivec2(0, 0), uvec2(0, 9)));
+        assert_eq!(
+            split_global_tile(ivec2(10, -1)),
+            (ivec2(1, -1), uvec2(0, 9))
+        );
+        assert_eq!(
+            split_global_tile(ivec2(-10, -11)),
+            (ivec2(-1, -2), uvec2(0, 9))
+        );
+    }
+
+    #[test]
+    fn test_split_global_pos_chunk_borders() {
+        assert_eq!(
+            split_global_pos(vec2(499.9, 0.0)),
+            (ivec2(0, 0), uvec2(9, 0))
+        );
+        assert_eq!(
+            split_global_pos(vec2(500.0, 0.0)),
+            (ivec2(1, 0), uvec2(0, 0))
+        );
+        assert_eq!(
+            split_global_pos(vec2(-500.0, -0.1)),
+            (ivec2(-1, -1), uvec2(0, 9))
+        );
+        //Used to round up to a tile index of 10 just under a border
+        assert_eq!(
+            split_global_pos(vec2(-1e-6, -1000.0)),
+            (ivec2(-1, -2), uvec2(9, 0))
+        );
+    }
+}
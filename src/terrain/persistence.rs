@@ -0,0 +1,426 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use bevy::{prelude::*, tasks::IoTaskPool};
+use bevy_ecs_tilemap::prelude::*;
+
+use super::{TILES_PRE_CHUNK, chunk_data::ChunkData, tile_index};
+use crate::{
+    chunk::{Chunk, ChunkPos},
+    player::{PlayerId, dig::DigDesignation},
+    terrain::tile_data::{TerrainType, TileHealth, TileType},
+};
+
+pub struct TerrainPersistencePlugin;
+impl Plugin for TerrainPersistencePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChunkStore>()
+            .add_observer(save_chunk_on_unload)
+            .add_systems(
+                Last,
+                (save_modified_chunks_on_exit, write_saved_chunks).chain(),
+            );
+    }
+}
+
+///Marks a chunk that has changed since it was generated or loaded
+///Only these chunks get written to disk when unloaded
+#[derive(Component, Default)]
+pub struct ChunkModified;
+
+type Region = HashMap<IVec3, ChunkData>;
+
+///Saves and loads ChunkData grouped into region files
+///Clones share the same regions so it can be moved into tasks that do the file I/O
+#[derive(Resource, Clone)]
+pub struct ChunkStore {
+    dir: PathBuf,
+    ///Saved chunks that aren't in a region file yet
+    unwritten: Arc<Mutex<Region>>,
+    regions: Arc<Mutex<RegionCache>>,
+}
+
+impl Default for ChunkStore {
+    fn default() -> Self {
+        Self::new("saves/world")
+    }
+}
+
+impl ChunkStore {
+    ///Chunks per side of a region
+    pub const REGION_SIZE: i32 = 16;
+    ///Regions kept in memory, the least recently used ones get dropped past this
+    pub const CACHED_REGIONS: usize = 16;
+    const MAGIC: &'static [u8; 4] = b"EXRG";
+    const VERSION: u8 = 2;
+
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            unwritten: default(),
+            regions: default(),
+        }
+    }
+
+    pub fn region_pos(chunk_pos: IVec3) -> IVec3 {
+        let IVec3 { x, y, z } = chunk_pos;
+        ivec3(
+            x.div_euclid(Self::REGION_SIZE),
+            y.div_euclid(Self::REGION_SIZE),
+            z,
+        )
+    }
+
+    fn region_path(&self, region_pos: IVec3) -> PathBuf {
+        let IVec3 { x, y, z } = region_pos;
+        self.dir.join(format!("r.{x}.{y}.{z}.region"))
+    }
+
+    ///Returns the saved data of the chunk if it was ever saved
+    ///Can read a region file so it belongs in a task
+    pub fn load(&self, chunk_pos: IVec3) -> Option<ChunkData> {
+        //Locked first so a write can't move the chunk out of unwritten before it is in its region
+        let mut regions = self.regions.lock().unwrap();
+        if let Some(data) = self.unwritten.lock().unwrap().get(&chunk_pos) {
+            return Some(data.clone());
+        }
+        let region_pos = Self::region_pos(chunk_pos);
+        let data = regions.get(self, region_pos).get(&chunk_pos).cloned();
+        regions.evict(Self::CACHED_REGIONS);
+        data
+    }
+
+    ///Keeps the chunk in memory until the next write
+    pub fn save(&self, chunk_pos: IVec3, data: ChunkData) {
+        self.unwritten.lock().unwrap().insert(chunk_pos, data);
+    }
+
+    pub fn has_unwritten(&self) -> bool {
+        !self.unwritten.lock().unwrap().is_empty()
+    }
+
+    ///Writes the saved chunks to their region files
+    ///Blocks on file I/O so it belongs in a task
+    pub fn write(&self) -> io::Result<()> {
+        let mut regions = self.regions.lock().unwrap();
+        let unwritten = std::mem::take(&mut *self.unwritten.lock().unwrap());
+        let mut changed = Vec::new();
+        for (chunk_pos, data) in unwritten {
+            let region_pos = Self::region_pos(chunk_pos);
+            regions.get(self, region_pos).insert(chunk_pos, data);
+            if !changed.contains(&region_pos) {
+                changed.push(region_pos);
+            }
+        }
+        if !changed.is_empty() {
+            fs::create_dir_all(&self.dir)?;
+        }
+        for region_pos in changed {
+            if regions.unwritable.contains(&region_pos) {
+                warn!(
+                    "not writing over unreadable region file:{}",
+                    self.region_path(region_pos).display()
+                );
+                continue;
+            }
+            let bytes = write_region(region_pos, regions.get(self, region_pos));
+            fs::write(self.region_path(region_pos), bytes)?;
+        }
+        //Everything in the cache is on disk now so any of it can go
+        regions.evict(Self::CACHED_REGIONS);
+        Ok(())
+    }
+}
+
+///Regions read from or written to disk, the last use of each is kept for eviction
+#[derive(Default)]
+struct RegionCache {
+    regions: HashMap<IVec3, (Region, u64)>,
+    uses: u64,
+    ///Regions whose file couldn't be read, never written so the chunks in the file aren't lost
+    unwritable: HashSet<IVec3>,
+}
+
+impl RegionCache {
+    ///Gets the region reading it from disk the first time it is asked for
+    fn get(&mut self, store: &ChunkStore, region_pos: IVec3) -> &mut Region {
+        self.uses += 1;
+        let (region, last_use) = self.regions.entry(region_pos).or_insert_with(|| {
+            let region = match read_region_file(&store.region_path(region_pos), region_pos) {
+                Some(region) => {
+                    self.unwritable.remove(&region_pos);
+                    region
+                }
+                None => {
+                    self.unwritable.insert(region_pos);
+                    Region::new()
+                }
+            };
+            (region, 0)
+        });
+        *last_use = self.uses;
+        region
+    }
+
+    ///Drops the least recently used regions until there are at most max
+    fn evict(&mut self, max: usize) {
+        while self.regions.len() > max {
+            let Some(&oldest) = self
+                .regions
+                .iter()
+                .min_by_key(|(_, (_, last_use))| *last_use)
+                .map(|(region_pos, _)| region_pos)
+            else {
+                return;
+            };
+            self.regions.remove(&oldest);
+        }
+    }
+}
+
+///Returns None if the file is there but can't be read
+///A corrupt file is moved aside so writing the region again doesn't replace it
+fn read_region_file(path: &Path, region_pos: IVec3) -> Option<Region> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Some(Region::new()),
+        Err(err) => {
+            warn!("failed to read region file:{}, {err}", path.display());
+            return None;
+        }
+    };
+    if let Some(region) = read_region(region_pos, &bytes) {
+        return Some(region);
+    }
+    let backup = path.with_extension("region.corrupt");
+    match fs::rename(path, &backup) {
+        Ok(()) => {
+            warn!("region file is corrupt, moved it to:{}", backup.display());
+            Some(Region::new())
+        }
+        Err(err) => {
+            warn!(
+                "region file is corrupt:{}, moving it failed:{err}",
+                path.display()
+            );
+            None
+        }
+    }
+}
+
+///Layout: magic, version, chunk count(u32 le), then per chunk its x y offset in the region
+///followed by its ChunkData bytes
+fn write_region(region_pos: IVec3, region: &Region) -> Vec<u8> {
+    let origin = region_pos.xy() * ChunkStore::REGION_SIZE;
+    let mut bytes = Vec::with_capacity(9 + region.len() * (2 + ChunkData::BYTES));
+    bytes.extend(ChunkStore::MAGIC);
+    bytes.push(ChunkStore::VERSION);
+    bytes.extend((region.len() as u32).to_le_bytes());
+    for (chunk_pos, data) in region {
+        let offset = chunk_pos.xy() - origin;
+        bytes.push(offset.x as u8);
+        bytes.push(offset.y as u8);
+        data.write_bytes(&mut bytes);
+    }
+    bytes
+}
+
+fn read_region(region_pos: IVec3, bytes: &[u8]) -> Option<Region> {
+    let rest = bytes.strip_prefix(ChunkStore::MAGIC)?;
+    let (&version, rest) = rest.split_first()?;
+    if version != ChunkStore::VERSION {
+        return None;
+    }
+    let count = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?);
+    let mut rest = &rest[4..];
+
+    let origin = region_pos.xy() * ChunkStore::REGION_SIZE;
+    let mut region = Region::new();
+    for _ in 0..count {
+        let &[x, y] = rest.get(..2)? else {
+            return None;
+        };
+        let data = ChunkData::read_bytes(&rest[2..])?;
+        let chunk_pos = (origin + ivec2(x as i32, y as i32)).extend(region_pos.z);
+        region.insert(chunk_pos, data);
+        rest = &rest[2 + ChunkData::BYTES..];
+    }
+    Some(region)
+}
+
+type SavedTile<'a> = (
+    &'a TileType,
+    &'a TerrainType,
+    &'a TileHealth,
+    Option<&'a DigDesignation>,
+);
+
+fn chunk_data_from_tiles(
+    tile_storage: &TileStorage,
+    tiles: &Query<SavedTile>,
+    players: &Query<&PlayerId>,
+) -> ChunkData {
+    let mut data = ChunkData::default();
+    for x in 0..TILES_PRE_CHUNK.x {
+        for y in 0..TILES_PRE_CHUNK.y {
+            let Some(tile_id) = tile_storage.get(&TilePos { x, y }) else {
+                continue;
+            };
+            let Ok((&tile_type, &terrain_type, &TileHealth(health), designation)) =
+                tiles.get(tile_id)
+            else {
+                continue;
+            };
+            data.set(uvec2(x, y), tile_type, terrain_type);
+            let index = tile_index(x, y) as usize;
+            data.health[index] = health;
+            data.digs[index] = designation.and_then(|designation| {
+                let &player = players.get(designation.player).ok()?;
+                Some((player, designation.priority))
+            });
+        }
+    }
+    data
+}
+
+fn save_chunk_on_unload(
+    trigger: Trigger<OnRemove, Chunk>,
+    chunks: Query<(&ChunkPos, &TileStorage), With<ChunkModified>>,
+    tiles: Query<SavedTile>,
+    players: Query<&PlayerId>,
+    store: Res<ChunkStore>,
+) {
+    let Ok((chunk_pos, tile_storage)) = chunks.get(trigger.target()) else {
+        return;
+    };
+    store.save(
+        **chunk_pos,
+        chunk_data_from_tiles(tile_storage, &tiles, &players),
+    );
+}
+
+///Everything still running gets dropped on exit so the write happens right here
+fn save_modified_chunks_on_exit(
+    mut exit: EventReader<AppExit>,
+    chunks: Query<(&ChunkPos, &TileStorage), With<ChunkModified>>,
+    tiles: Query<SavedTile>,
+    players: Query<&PlayerId>,
+    store: Res<ChunkStore>,
+) {
+    if exit.read().last().is_none() {
+        return;
+    }
+    for (chunk_pos, tile_storage) in chunks.iter() {
+        store.save(
+            **chunk_pos,
+            chunk_data_from_tiles(tile_storage, &tiles, &players),
+        );
+    }
+    if let Err(err) = store.write() {
+        warn!("failed to write chunks:{err}");
+    }
+}
+
+///Writes the chunks saved this frame off the main thread
+fn write_saved_chunks(store: Res<ChunkStore>) {
+    if !store.has_unwritten() {
+        return;
+    }
+    let store = store.clone();
+    IoTaskPool::get()
+        .spawn(async move {
+            if let Err(err) = store.write() {
+                warn!("failed to write chunks:{err}");
+            }
+        })
+        .detach();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_pos_negative() {
+        assert_eq!(ChunkStore::region_pos(ivec3(0, 15, 0)), ivec3(0, 0, 0));
+        assert_eq!(ChunkStore::region_pos(ivec3(16, -1, 2)), ivec3(1, -1, 2));
+        assert_eq!(ChunkStore::region_pos(ivec3(-16, -17, 0)), ivec3(-1, -2, 0));
+    }
+
+    #[test]
+    fn test_chunk_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("expance_chunk_store_{}", std::process::id()));
+        let mut data = ChunkData::default();
+        data.set(uvec2(4, 2), TileType::Ground, TerrainType::Sand);
+
+        let store = ChunkStore::new(&dir);
+        store.save(ivec3(-3, 20, 0), data.clone());
+        //Saved chunks can be loaded before they are written
+        assert_eq!(store.load(ivec3(-3, 20, 0)), Some(data.clone()));
+        store.save(ivec3(-4, 20, 0), ChunkData::default());
+        store.write().unwrap();
+        assert!(!store.has_unwritten());
+
+        //A fresh store has to read it back from disk
+        let store = ChunkStore::new(&dir);
+        assert_eq!(store.load(ivec3(-3, 20, 0)), Some(data));
+        assert_eq!(store.load(ivec3(-4, 20, 0)), Some(ChunkData::default()));
+        assert_eq!(store.load(ivec3(-5, 20, 0)), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_region_cache_evicts_least_recently_used() {
+        let dir = std::env::temp_dir().join(format!("expance_region_cache_{}", std::process::id()));
+        let store = ChunkStore::new(&dir);
+        let region_chunk = |x: i32| ivec3(x * ChunkStore::REGION_SIZE, 0, 0);
+        for x in 0..ChunkStore::CACHED_REGIONS as i32 {
+            store.save(region_chunk(x), ChunkData::default());
+        }
+        store.write().unwrap();
+        //Writing uses the regions in any order so use them again in order
+        for x in 0..ChunkStore::CACHED_REGIONS as i32 {
+            store.load(region_chunk(x));
+        }
+        //Region 0 gets used again so region 1 is the oldest
+        store.load(region_chunk(0));
+        store.load(region_chunk(ChunkStore::CACHED_REGIONS as i32));
+
+        {
+            let cache = store.regions.lock().unwrap();
+            assert_eq!(cache.regions.len(), ChunkStore::CACHED_REGIONS);
+            assert!(cache.regions.contains_key(&ivec3(0, 0, 0)));
+            assert!(!cache.regions.contains_key(&ivec3(1, 0, 0)));
+        }
+
+        //Evicted regions are still on disk
+        assert_eq!(store.load(region_chunk(1)), Some(ChunkData::default()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_region_is_kept() {
+        let dir =
+            std::env::temp_dir().join(format!("expance_corrupt_region_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = ChunkStore::new(&dir);
+        let path = store.region_path(IVec3::ZERO);
+        fs::write(&path, b"not a region").unwrap();
+
+        assert_eq!(store.load(IVec3::ZERO), None);
+        store.save(IVec3::ZERO, ChunkData::default());
+        store.write().unwrap();
+
+        //The corrupt file is still there for someone to look at
+        let backup = fs::read(path.with_extension("region.corrupt")).unwrap();
+        assert_eq!(backup, b"not a region");
+        let store = ChunkStore::new(&dir);
+        assert_eq!(store.load(IVec3::ZERO), Some(ChunkData::default()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use super::{
+    TILE_HIGHLIGHT_Z, TILE_SIZE, TileGrid, global_tile_at, global_tile_center, split_global_tile,
+};
+use crate::{app::AppUpdate, chunk::ChunkLayer, cursor::CurrsorPositon};
+
+pub struct TilePickingPlugin;
+impl Plugin for TilePickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HoveredTile>()
+            .add_systems(Startup, spawn_tile_highlight)
+            .add_systems(
+                Update,
+                (update_hovered_tile, snap_tile_highlight)
+                    .chain()
+                    .in_set(AppUpdate::Data),
+            );
+    }
+}
+
+///The tile under the cursor on the current ChunkLayer
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HoveredTile {
+    pub global: IVec2,
+    pub layer: i32,
+    pub tile_pos: TilePos,
+    ///None while the chunk isn't loaded
+    pub chunk: Option<Entity>,
+    ///None while the chunk isn't loaded or is still generating
+    pub tile: Option<Entity>,
+}
+
+impl HoveredTile {
+    pub fn at(pos: Vec2, layer: i32, tiles: &TileGrid) -> Self {
+        let global = global_tile_at(pos);
+        let (chunk_pos, tile_pos) = split_global_tile(global);
+        let tile_pos = TilePos::from(tile_pos);
+        let chunk = tiles.chunk_at(chunk_pos, layer);
+        Self {
+            global,
+            layer,
+            tile_pos,
+            chunk,
+            tile: chunk.and_then(|chunk| tiles.tile_in_chunk(chunk, tile_pos)),
+        }
+    }
+}
+
+fn update_hovered_tile(
+    cursor: Res<CurrsorPositon>,
+    chunk_layer: Res<ChunkLayer>,
+    tiles: TileGrid,
+    mut hovered: ResMut<HoveredTile>,
+) {
+    //Only changed when it is different so is_changed means a new tile
+    hovered.set_if_neq(HoveredTile::at(**cursor, **chunk_layer, &tiles));
+}
+
+///Sprite drawn over the HoveredTile
+#[derive(Component)]
+#[require(Transform)]
+pub struct TileHighlight;
+
+impl TileHighlight {
+    const COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.2);
+}
+
+fn spawn_tile_highlight(mut commands: Commands) {
+    commands.spawn((
+        TileHighlight,
+        Sprite::from_color(TileHighlight::COLOR, TILE_SIZE),
+        Visibility::Hidden,
+    ));
+}
+
+fn snap_tile_highlight(
+    hovered: Res<HoveredTile>,
+    mut highlight: Query<(&mut Transform, &mut Visibility), With<TileHighlight>>,
+) {
+    if !hovered.is_changed() {
+        return;
+    }
+    let Ok((mut transform, mut visibility)) = highlight.single_mut() else {
+        return;
+    };
+    transform.translation =
+        global_tile_center(hovered.global).extend(hovered.layer as f32 + TILE_HIGHLIGHT_Z);
+    *visibility = if hovered.tile.is_some() {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::ChunkManager;
+
+    #[test]
+    fn test_hovered_tile_across_chunk_border() {
+        let mut app = App::new();
+        app.init_resource::<ChunkManager>()
+            .init_resource::<ChunkLayer>()
+            .init_resource::<CurrsorPositon>()
+            .init_resource::<HoveredTile>()
+            .add_systems(Update, update_hovered_tile);
+
+        //Chunk (-1, -1) is loaded with one tile in its top right corner
+        let mut storage = TileStorage::empty(TilemapSize { x: 10, y: 10 });
+        let corner = TilePos { x: 9, y: 9 };
+        let tile = app.world_mut().spawn_empty().id();
+        storage.set(&corner, tile);
+        let chunk = app.world_mut().spawn(storage).id();
+        app.world_mut()
+            .resource_mut::<ChunkManager>()
+            .insert(ivec3(-1, -1, 0), chunk);
+
+        let hover = |app: &mut App, pos: Vec2| {
+            **app.world_mut().resource_mut::<CurrsorPositon>() = pos;
+            app.update();
+            *app.world().resource::<HoveredTile>()
+        };
+
+        let hovered = hover(&mut app, vec2(-0.1, -0.1));
+        assert_eq!(hovered.global, ivec2(-1, -1));
+        assert_eq!(hovered.tile_pos, corner);
+        assert_eq!(hovered.chunk, Some(chunk));
+        assert_eq!(hovered.tile, Some(tile));
+
+        //Just over the border is chunk (0, 0) which isn't loaded
+        let hovered = hover(&mut app, vec2(0.0, 0.0));
+        assert_eq!(hovered.global, ivec2(0, 0));
+        assert_eq!(hovered.tile_pos, TilePos { x: 0, y: 0 });
+        assert_eq!(hovered.chunk, None);
+        assert_eq!(hovered.tile, None);
+
+        //Bottom left corner of the same chunk has no tile yet
+        let hovered = hover(&mut app, vec2(-500.0, -500.0));
+        assert_eq!(hovered.global, ivec2(-10, -10));
+        assert_eq!(hovered.tile_pos, TilePos { x: 0, y: 0 });
+        assert_eq!(hovered.chunk, Some(chunk));
+        assert_eq!(hovered.tile, None);
+    }
+}
@@ -1,24 +1,30 @@
 use bevy::{
-    ecs::{component::HookContext, world::DeferredWorld},
+    ecs::{component::HookContext, system::SystemParam, world::DeferredWorld},
     prelude::*,
 };
 use bevy_ecs_tilemap::prelude::*;
+use strum::FromRepr;
 
-use super::TILE_SIZE;
+use super::{
+    TILE_SIZE, generation::WorldGenSettings, persistence::ChunkModified, split_global_pos,
+    split_global_tile,
+};
 use crate::{
     app::AppUpdate,
-    chunk::{Chunk, ChunkManager, ChunkPos},
+    chunk::{Chunk, ChunkManager},
 };
 
 pub struct TerrainDataPlugin;
 impl Plugin for TerrainDataPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<BrakeTile>()
+            .add_event::<TileBroken>()
             .add_systems(Update, brake_tile.in_set(AppUpdate::PostAction));
     }
 }
 
-#[derive(Clone, Copy, Default, Component)]
+#[derive(Clone, Copy, Default, Component, Debug, PartialEq, Eq, FromRepr)]
+#[repr(u8)]
 #[require(TileTextureIndex)]
 #[component(
     immutable,
@@ -31,6 +37,10 @@ pub enum TileType {
 }
 
 impl TileType {
+    ///Texture of the first crack stage, the other stages follow it in the atlas
+    pub const CRACK_TEXTURE_START: u32 = 4;
+    pub const CRACK_STAGES: u32 = 3;
+
     pub fn get_texture_index(&self) -> u32 {
         match self {
             TileType::Wall => 1,
@@ -38,8 +48,18 @@ impl TileType {
         }
     }
 
-    pub fn generate(x: u32, y: u32, chunk_pos: IVec3) -> Self {
-        Self::Wall
+    ///Texture of a wall with health left out of hardness
+    pub fn get_crack_texture_index(health: u32, hardness: u32) -> u32 {
+        if health >= hardness {
+            return TileType::Wall.get_texture_index();
+        }
+        let damaged = 1.0 - health as f32 / hardness as f32;
+        let stage = (damaged * Self::CRACK_STAGES as f32).ceil() as u32;
+        Self::CRACK_TEXTURE_START + stage.clamp(1, Self::CRACK_STAGES) - 1
+    }
+
+    pub fn generate(x: u32, y: u32, chunk_pos: IVec3, settings: &WorldGenSettings) -> Self {
+        settings.tile_type_at(WorldGenSettings::global_tile(x, y, chunk_pos), chunk_pos.z)
     }
 }
 
@@ -54,7 +74,8 @@ fn on_tile_type_replace(mut world: DeferredWorld, HookContext { entity, .. }: Ho
         .insert(TileTextureIndex(texture_index));
 }
 
-#[derive(Clone, Copy, Default, Component)]
+#[derive(Clone, Copy, Default, Component, Debug, PartialEq, Eq, FromRepr)]
+#[repr(u8)]
 #[require(TileColor)]
 #[component(
     immutable,
@@ -76,8 +97,17 @@ impl TerrainType {
         }
     }
 
-    pub fn generate(x: u32, y: u32, chunk_pos: IVec3) -> Self {
-        TerrainType::Dirt
+    ///Damage a wall of this terrain takes before braking
+    pub fn hardness(&self) -> u32 {
+        match self {
+            TerrainType::Stone => 30,
+            TerrainType::Dirt => 12,
+            TerrainType::Sand => 5,
+        }
+    }
+
+    pub fn generate(x: u32, y: u32, chunk_pos: IVec3, settings: &WorldGenSettings) -> Self {
+        settings.terrain_type_at(WorldGenSettings::global_tile(x, y, chunk_pos), chunk_pos.z)
     }
 }
 
@@ -86,54 +116,136 @@ fn on_terrain_type_replace(mut world: DeferredWorld, HookContext { entity, .. }:
     world.get_mut::<TileColor>(entity).unwrap().0 = terrain_color;
 }
 
+///Looks up tiles by global tile coordinate
+#[derive(SystemParam)]
+pub struct TileGrid<'w, 's> {
+    chunk_manager: Res<'w, ChunkManager>,
+    chunks: Query<'w, 's, &'static TileStorage>,
+    tiles: Query<'w, 's, &'static TileType>,
+}
+
+impl TileGrid<'_, '_> {
+    ///Returns None if the chunk is not loaded
+    pub fn chunk_at(&self, chunk_pos: IVec2, layer: i32) -> Option<Entity> {
+        self.chunk_manager.get(chunk_pos.extend(layer))
+    }
+
+    ///Returns None if the chunk is not loaded or still generating
+    pub fn tile_in_chunk(&self, chunk_id: Entity, tile_pos: TilePos) -> Option<Entity> {
+        self.chunks.get(chunk_id).ok()?.get(&tile_pos)
+    }
+
+    ///Returns None if the chunk is not loaded or still generating
+    pub fn tile_at(&self, tile: IVec2, layer: i32) -> Option<Entity> {
+        let (chunk_pos, tile_pos) = split_global_tile(tile);
+        let chunk_id = self.chunk_at(chunk_pos, layer)?;
+        self.tile_in_chunk(chunk_id, TilePos::from(tile_pos))
+    }
+
+    pub fn tile_type_at(&self, tile: IVec2, layer: i32) -> Option<TileType> {
+        self.tiles.get(self.tile_at(tile, layer)?).ok().copied()
+    }
+}
+
+///Damage a wall can still take before braking
+#[derive(Component, Clone, Copy, Deref, DerefMut, Debug)]
+pub struct TileHealth(pub u32);
+
+///Damages a wall
 #[derive(Event, Clone, Copy)]
 pub enum BrakeTile {
-    ByEntity(Entity),
-    ByPos { point: Vec2, layer: i32 },
+    ByEntity {
+        tile: Entity,
+        damage: u32,
+    },
+    ByPos {
+        point: Vec2,
+        layer: i32,
+        damage: u32,
+    },
+}
+
+impl BrakeTile {
+    ///Enough damage to brake any wall at once
+    pub const INSTANT: u32 = u32::MAX;
+
+    pub fn damage(&self) -> u32 {
+        match self {
+            BrakeTile::ByEntity { damage, .. } | BrakeTile::ByPos { damage, .. } => *damage,
+        }
+    }
+}
+
+///Sent when a wall's health reaches zero and it turns into Ground
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TileBroken {
+    pub tile: Entity,
 }
 
 fn brake_tile(
     mut events: EventReader<BrakeTile>,
-    chunks: Query<&TileStorage>,
+    mut waiting: Local<Vec<BrakeTile>>,
+    chunks: Query<Option<&TileStorage>, With<Chunk>>,
+    mut tiles: Query<(
+        &TilemapId,
+        &TileType,
+        &TerrainType,
+        &mut TileHealth,
+        &mut TileTextureIndex,
+    )>,
     chunk_manager: Res<ChunkManager>,
+    mut broken: EventWriter<TileBroken>,
     mut commands: Commands,
 ) {
-    for event in events.read() {
+    //Brakes on chunks that were still generating get another go
+    let retry: Vec<BrakeTile> = waiting.drain(..).collect();
+    for event in retry.iter().chain(events.read()) {
         let tile_id = match event {
-            BrakeTile::ByEntity(tile_id) => *tile_id,
-            BrakeTile::ByPos { point, layer } => {
-                let Some(chunk_id) = chunk_manager.get_chunk_at(point, *layer) else {
+            BrakeTile::ByEntity { tile, .. } => *tile,
+            BrakeTile::ByPos { point, layer, .. } => {
+                let (chunk_pos, tile_pos) = split_global_pos(*point);
+                let Some(chunk_id) = chunk_manager.get(chunk_pos.extend(*layer)) else {
                     warn!("no chunk at point:{point}, layer:{layer}");
                     continue;
                 };
                 let Ok(tile_storage) = chunks.get(chunk_id) else {
-                    warn!("chunk has no tilestorage:{chunk_id}");
+                    warn!("chunk not found:{chunk_id}");
+                    continue;
+                };
+                let Some(tile_storage) = tile_storage else {
+                    //The chunk is still generating
+                    waiting.push(*event);
                     continue;
                 };
-                let tile_index = get_tile_chunk_index(point);
-                let Some(tile_id) = tile_storage.get(&TilePos::from(tile_index)) else {
-                    warn!("no tile at pos:{tile_index}");
+                let Some(tile_id) = tile_storage.get(&TilePos::from(tile_pos)) else {
+                    warn!("no tile at pos:{tile_pos}");
                     continue;
                 };
                 tile_id
             }
         };
+        let Ok((&TilemapId(chunk_id), tile_type, terrain_type, mut health, mut texture)) =
+            tiles.get_mut(tile_id)
+        else {
+            warn!("tile not found:{tile_id}");
+            continue;
+        };
+        //Already ground or broken earlier this frame
+        if *tile_type != TileType::Wall || **health == 0 {
+            continue;
+        }
+        **health = health.saturating_sub(event.damage());
+        //Damaged walls are saved too
+        commands.entity(chunk_id).insert(ChunkModified);
+        if **health > 0 {
+            texture.0 = TileType::get_crack_texture_index(**health, terrain_type.hardness());
+            continue;
+        }
         commands.entity(tile_id).insert(TileType::Ground);
+        broken.write(TileBroken { tile: tile_id });
     }
 }
 
-//Think this should work if there are picking errors check here first
-fn get_tile_chunk_index(pos: &Vec2) -> UVec2 {
-    let mut local_pos = pos % Chunk::SIZE;
-    if local_pos.x.is_sign_negative() {
-        local_pos.x = Chunk::SIZE.x + local_pos.x;
-    }
-    if local_pos.y.is_sign_negative() {
-        local_pos.y = Chunk::SIZE.y + local_pos.y;
-    }
-    (local_pos / TILE_SIZE).as_uvec2()
-}
-
 ///Brake all tiles around point by the range.
 pub fn brake_all_tiles_around(
     point: Vec2,
@@ -147,7 +259,65 @@ pub fn brake_all_tiles_around(
             out.write(BrakeTile::ByPos {
                 point: point + (Vec2::new(x as f32, y as f32) * TILE_SIZE),
                 layer: level,
+                damage: BrakeTile::INSTANT,
             });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::ChunkPos;
+
+    #[test]
+    fn test_crack_texture_index() {
+        let wall = TileType::Wall.get_texture_index();
+        assert_eq!(TileType::get_crack_texture_index(30, 30), wall);
+        assert_eq!(TileType::get_crack_texture_index(29, 30), 4);
+        assert_eq!(TileType::get_crack_texture_index(21, 30), 4);
+        assert_eq!(TileType::get_crack_texture_index(19, 30), 5);
+        assert_eq!(TileType::get_crack_texture_index(1, 30), 6);
+    }
+
+    #[test]
+    fn test_brake_tile_damage() {
+        let mut app = App::new();
+        app.init_resource::<ChunkManager>()
+            .add_event::<BrakeTile>()
+            .add_event::<TileBroken>()
+            .add_systems(Update, brake_tile);
+
+        let chunk = app.world_mut().spawn((Chunk, ChunkPos(IVec3::ZERO))).id();
+        let terrain = TerrainType::Dirt;
+        let tile = app
+            .world_mut()
+            .spawn((
+                TileType::Wall,
+                terrain,
+                TileHealth(terrain.hardness()),
+                TilemapId(chunk),
+            ))
+            .id();
+        let damage = |app: &mut App, damage: u32| {
+            app.world_mut()
+                .send_event(BrakeTile::ByEntity { tile, damage });
+            app.update();
+        };
+
+        damage(&mut app, 5);
+        damage(&mut app, 5);
+        let world = app.world();
+        assert_eq!(world.get::<TileHealth>(tile).unwrap().0, 2);
+        assert_eq!(world.get::<TileType>(tile), Some(&TileType::Wall));
+        assert_eq!(world.get::<TileTextureIndex>(tile).unwrap().0, 6);
+        assert!(world.resource::<Events<TileBroken>>().is_empty());
+
+        damage(&mut app, 5);
+        let world = app.world();
+        assert_eq!(world.get::<TileType>(tile), Some(&TileType::Ground));
+        assert_eq!(world.get::<TileTextureIndex>(tile).unwrap().0, 2);
+        assert!(world.get::<ChunkModified>(chunk).is_some());
+        assert_eq!(world.resource::<Events<TileBroken>>().len(), 1);
+    }
+}
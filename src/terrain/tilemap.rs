@@ -1,53 +1,147 @@
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
 use bevy_ecs_tilemap::prelude::*;
 
-use super::{TILE_SIZE, TILES_PRE_CHUNK, TerrainTileAtlas};
+use super::{
+    TILE_SIZE, TILES_PRE_CHUNK, TerrainTileAtlas, chunk_data::ChunkData,
+    generation::WorldGenSettings, persistence::ChunkStore,
+};
 use crate::{
+    app::AppUpdate,
     chunk::{Chunk, ChunkPos},
-    terrain::tile_data::{TerrainType, TileType},
+    input::{InputPlayback, live_input},
+    player::{PlayerId, dig::DigDesignation},
+    terrain::tile_data::{TileHealth, TileType},
 };
 
 pub struct TerrainTilemapPlugin;
 impl Plugin for TerrainTilemapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_observer(add_tilemap_to_chunk);
+        app.init_resource::<ChunkMaterializeBudget>()
+            .add_observer(start_chunk_generation)
+            .add_systems(Update, add_tilemap_to_chunk.in_set(AppUpdate::Data));
     }
 }
 
-fn add_tilemap_to_chunk(
+///A Chunk whose tiles are still being generated off the main thread
+///Dropping it (e.g. the chunk gets unloaded) cancels the generation
+#[derive(Component)]
+pub struct PendingChunk(Task<ChunkData>);
+
+///Max generated chunks that get their tilemap spawned per frame
+#[derive(Resource, Deref, DerefMut)]
+pub struct ChunkMaterializeBudget(pub usize);
+impl Default for ChunkMaterializeBudget {
+    fn default() -> Self {
+        Self(4)
+    }
+}
+
+fn start_chunk_generation(
     trigger: Trigger<OnAdd, Chunk>,
     mut commands: Commands,
-    chunks: Query<(&ChunkPos, &Transform)>,
-    tile_map_atalas: Res<TerrainTileAtlas>,
+    chunks: Query<&ChunkPos>,
+    chunk_store: Res<ChunkStore>,
+    world_gen: Res<WorldGenSettings>,
 ) {
     let chunk_id = trigger.target();
-    let Ok((chunk_pos, transform)) = chunks.get(chunk_id) else {
+    let Ok(&ChunkPos(chunk_pos)) = chunks.get(chunk_id) else {
         warn!("chunk not found");
         return;
     };
+    let chunk_store = chunk_store.clone();
+    let world_gen = world_gen.clone();
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        //Use the saved chunk if it was modified before
+        chunk_store
+            .load(chunk_pos)
+            .unwrap_or_else(|| ChunkData::generate(chunk_pos, &world_gen))
+    });
+    commands.entity(chunk_id).insert(PendingChunk(task));
+}
+
+fn add_tilemap_to_chunk(
+    mut commands: Commands,
+    mut chunks: Query<(Entity, &mut PendingChunk, &Transform)>,
+    tile_map_atalas: Res<TerrainTileAtlas>,
+    budget: Res<ChunkMaterializeBudget>,
+    players: Query<(Entity, &PlayerId)>,
+    playback: Option<Res<InputPlayback>>,
+) {
+    //Recordings and replays wait for every chunk so the tiles show up on the same frame each time
+    let frame_locked = !live_input(playback);
+    let mut materialized = 0;
+    for (chunk_id, mut pending, transform) in chunks.iter_mut() {
+        if materialized >= **budget && !frame_locked {
+            break;
+        }
+        let chunk_data = if frame_locked {
+            block_on(&mut pending.0)
+        } else {
+            let Some(chunk_data) = block_on(future::poll_once(&mut pending.0)) else {
+                continue;
+            };
+            chunk_data
+        };
+        commands.entity(chunk_id).remove::<PendingChunk>();
+        spawn_chunk_tilemap(
+            &mut commands,
+            chunk_id,
+            *transform,
+            &chunk_data,
+            &tile_map_atalas,
+            &players,
+        );
+        materialized += 1;
+    }
+}
+
+fn spawn_chunk_tilemap(
+    commands: &mut Commands,
+    chunk_id: Entity,
+    transform: Transform,
+    chunk_data: &ChunkData,
+    tile_map_atalas: &TerrainTileAtlas,
+    players: &Query<(Entity, &PlayerId)>,
+) {
     let mut tile_storage = TileStorage::empty(TILES_PRE_CHUNK.into());
     //build all tiles
     for x in 0..TILES_PRE_CHUNK.x {
         for y in 0..TILES_PRE_CHUNK.y {
-            let tile_type = TileType::generate(x, y, **chunk_pos);
-            let terrain_type = TerrainType::generate(x, y, **chunk_pos);
+            let (tile_type, terrain_type) = chunk_data.get(uvec2(x, y));
+            let health = chunk_data.health(uvec2(x, y));
 
             let texture_index = TileTextureIndex(tile_type.get_texture_index());
             let color = TileColor(terrain_type.get_color());
             let tile_pos = TilePos { x, y };
-            let tile_entity = commands
-                .spawn((
-                    TileBundle {
-                        position: tile_pos,
-                        tilemap_id: TilemapId(chunk_id),
-                        texture_index,
-                        color,
-                        ..Default::default()
-                    },
-                    tile_type,
-                    terrain_type,
-                ))
-                .id();
+            let mut tile = commands.spawn((
+                TileBundle {
+                    position: tile_pos,
+                    tilemap_id: TilemapId(chunk_id),
+                    texture_index,
+                    color,
+                    ..Default::default()
+                },
+                tile_type,
+                terrain_type,
+                TileHealth(health),
+            ));
+            //Inserted after TileType so it isn't replaced by the undamaged texture
+            if tile_type == TileType::Wall && health < terrain_type.hardness() {
+                tile.insert(TileTextureIndex(TileType::get_crack_texture_index(
+                    health,
+                    terrain_type.hardness(),
+                )));
+            }
+            //The player might be gone since it was marked
+            if let Some((player_id, priority)) = chunk_data.dig(uvec2(x, y))
+                && let Some((player, _)) = players.iter().find(|&(_, &id)| id == player_id)
+            {
+                tile.insert(DigDesignation { player, priority });
+            }
+            let tile_entity = tile.id();
             commands.entity(chunk_id).add_child(tile_entity);
             tile_storage.set(&tile_pos, tile_entity);
         }
@@ -60,7 +154,7 @@ fn add_tilemap_to_chunk(
         storage: tile_storage,
         texture: TilemapTexture::Single(tile_map_atalas.texture.clone()),
         tile_size: TILE_SIZE.into(),
-        transform: *transform,
+        transform,
         anchor: TilemapAnchor::BottomLeft,
         render_settings: TilemapRenderSettings {
             render_chunk_size: TILES_PRE_CHUNK,